@@ -0,0 +1,104 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::aiff::{
+    read_extended, read_pstring, write_extended, write_pstring, AiffChunk, AiffFile, AiffForm,
+};
+use crate::{ChunkType, WavTagError};
+
+/// The common chunk describes the sample data, like the WAV format chunk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommonChunk {
+    pub channels: u16,
+
+    /// The number of sample frames in the sound data chunk.
+    pub sample_frames: u32,
+
+    /// The number of bits in each sample, 1-32 for uncompressed data.
+    pub sample_size: u16,
+
+    pub sample_rate: f64,
+
+    /// How the samples are encoded in an AIFC file, eg. `NONE`, `sowt` (little-endian) or
+    /// `fl32`. Plain AIFF is always `NONE`.
+    pub compression_type: [u8; 4],
+
+    /// A human readable name for the compression type.
+    pub compression_name: String,
+}
+
+impl Default for CommonChunk {
+    fn default() -> Self {
+        CommonChunk {
+            channels: 1,
+            sample_frames: 0,
+            sample_size: 16,
+            sample_rate: 44100.0,
+            compression_type: *b"NONE",
+            compression_name: "not compressed".to_string(),
+        }
+    }
+}
+
+impl CommonChunk {
+    pub fn from_chunk(chunk: &AiffChunk) -> Result<Self, WavTagError> {
+        chunk.check_tag(*b"COMM")?;
+
+        Self::read_body(&chunk.data).map_err(|e| chunk.body_error(e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+
+        let mut common = CommonChunk {
+            channels: data.read_u16::<BigEndian>()?,
+            sample_frames: data.read_u32::<BigEndian>()?,
+            sample_size: data.read_u16::<BigEndian>()?,
+            sample_rate: read_extended(&mut data)?,
+            ..CommonChunk::default()
+        };
+
+        // only AIFC has the compression fields.
+        if data.position() < body.len() as u64 {
+            data.read_exact(&mut common.compression_type)?;
+            common.compression_name = read_pstring(&mut data)?;
+        }
+
+        Ok(common)
+    }
+
+    /// The compression fields are only written for AIFC.
+    pub fn serialise(&self, form: AiffForm) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(18);
+
+        // writes to a vec are infallible.
+        chunk.write_u16::<BigEndian>(self.channels).unwrap();
+        chunk.write_u32::<BigEndian>(self.sample_frames).unwrap();
+        chunk.write_u16::<BigEndian>(self.sample_size).unwrap();
+        write_extended(&mut chunk, self.sample_rate);
+
+        if form == AiffForm::Aifc {
+            chunk.extend_from_slice(&self.compression_type);
+            write_pstring(&mut chunk, &self.compression_name);
+        }
+
+        chunk
+    }
+}
+
+impl AiffFile {
+    pub fn common(&self) -> Result<CommonChunk, WavTagError> {
+        match self.find_chunk(*b"COMM") {
+            Some(c) => CommonChunk::from_chunk(c),
+            None => Err(WavTagError::MissingChunk(ChunkType::Unknown(
+                "COMM".to_string(),
+            ))),
+        }
+    }
+
+    pub fn set_common(&mut self, chunk: CommonChunk) {
+        self.add_or_replace_chunk(AiffChunk::new(*b"COMM", chunk.serialise(self.form)));
+    }
+}
@@ -0,0 +1,94 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::aiff::{AiffChunk, AiffFile};
+use crate::WavTagError;
+
+/// The comments chunk holds text comments, optionally attached to a marker.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommentsChunk {
+    pub comments: Vec<AiffComment>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AiffComment {
+    /// When the comment was written, in seconds since January 1, 1904.
+    pub timestamp: u32,
+
+    /// The marker the comment refers to, or 0 for the whole file.
+    pub marker_id: u16,
+
+    pub text: String,
+}
+
+impl CommentsChunk {
+    pub fn from_chunk(chunk: &AiffChunk) -> Result<Self, WavTagError> {
+        chunk.check_tag(*b"COMT")?;
+
+        Self::read_body(&chunk.data).map_err(|e| chunk.body_error(e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+        let num_comments = data.read_u16::<BigEndian>()?;
+
+        let comments = (0..num_comments)
+            .map(|_| {
+                let timestamp = data.read_u32::<BigEndian>()?;
+                let marker_id = data.read_u16::<BigEndian>()?;
+
+                // the text is padded to an even length.
+                let len = data.read_u16::<BigEndian>()? as usize;
+                let mut text = vec![0u8; len + len % 2];
+                data.read_exact(&mut text)?;
+                text.truncate(len);
+
+                Ok(AiffComment {
+                    timestamp,
+                    marker_id,
+                    text: String::from_utf8_lossy(&text).into_owned(),
+                })
+            })
+            .collect::<Result<Vec<AiffComment>, io::Error>>()?;
+
+        Ok(CommentsChunk { comments })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::new();
+
+        // writes to a vec are infallible.
+        chunk
+            .write_u16::<BigEndian>(self.comments.len() as u16)
+            .unwrap();
+
+        for comment in self.comments.iter() {
+            let text = &comment.text.as_bytes()[..comment.text.len().min(u16::MAX as usize)];
+
+            chunk.write_u32::<BigEndian>(comment.timestamp).unwrap();
+            chunk.write_u16::<BigEndian>(comment.marker_id).unwrap();
+            chunk.write_u16::<BigEndian>(text.len() as u16).unwrap();
+            chunk.extend_from_slice(text);
+            if text.len() % 2 != 0 {
+                chunk.push(0);
+            }
+        }
+
+        chunk
+    }
+}
+
+impl AiffFile {
+    pub fn get_comments_chunk(&self) -> Result<CommentsChunk, WavTagError> {
+        match self.find_chunk(*b"COMT") {
+            Some(c) => CommentsChunk::from_chunk(c),
+            None => Ok(CommentsChunk::default()),
+        }
+    }
+
+    pub fn set_comments_chunk(&mut self, chunk: CommentsChunk) {
+        self.add_or_replace_chunk(AiffChunk::new(*b"COMT", chunk.serialise()));
+    }
+}
@@ -0,0 +1,162 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::Cursor;
+
+use crate::aiff::{AiffChunk, AiffFile};
+use crate::WavTagError;
+
+/// The AIFF instrument chunk, the equivalent of the WAV inst and smpl chunks together. Its loops
+/// start and end at markers in the marker chunk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AiffInstrumentChunk {
+    /// The MIDI note at which the sample plays back at its original pitch.
+    pub base_note: u8,
+
+    /// Fine tuning in cents, -50 to +50.
+    pub detune: i8,
+
+    pub low_note: u8,
+    pub high_note: u8,
+    pub low_velocity: u8,
+    pub high_velocity: u8,
+
+    /// The gain in dB.
+    pub gain: i16,
+
+    /// The loop played while the note is held.
+    pub sustain_loop: AiffLoop,
+
+    /// The loop played after the note is released.
+    pub release_loop: AiffLoop,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AiffLoop {
+    pub play_mode: PlayMode,
+
+    /// The id of the marker the loop starts at.
+    pub begin_loop: u16,
+
+    /// The id of the marker the loop ends at. The frame after the marker isn't played.
+    pub end_loop: u16,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayMode {
+    #[default]
+    NoLooping,
+    Forward,
+    ForwardBackward,
+    Other(u16),
+}
+
+impl From<u16> for PlayMode {
+    fn from(play_mode: u16) -> Self {
+        match play_mode {
+            0 => PlayMode::NoLooping,
+            1 => PlayMode::Forward,
+            2 => PlayMode::ForwardBackward,
+            other => PlayMode::Other(other),
+        }
+    }
+}
+
+impl From<PlayMode> for u16 {
+    fn from(play_mode: PlayMode) -> Self {
+        match play_mode {
+            PlayMode::NoLooping => 0,
+            PlayMode::Forward => 1,
+            PlayMode::ForwardBackward => 2,
+            PlayMode::Other(other) => other,
+        }
+    }
+}
+
+impl Default for AiffInstrumentChunk {
+    fn default() -> Self {
+        AiffInstrumentChunk {
+            base_note: 60,
+            detune: 0,
+            low_note: 0,
+            high_note: 127,
+            low_velocity: 1,
+            high_velocity: 127,
+            gain: 0,
+            sustain_loop: AiffLoop::default(),
+            release_loop: AiffLoop::default(),
+        }
+    }
+}
+
+impl AiffLoop {
+    fn read<R: io::Read>(data: &mut R) -> Result<Self, io::Error> {
+        Ok(AiffLoop {
+            play_mode: data.read_u16::<BigEndian>()?.into(),
+            begin_loop: data.read_u16::<BigEndian>()?,
+            end_loop: data.read_u16::<BigEndian>()?,
+        })
+    }
+
+    fn write(&self, chunk: &mut Vec<u8>) {
+        // writes to a vec are infallible.
+        chunk.write_u16::<BigEndian>(self.play_mode.into()).unwrap();
+        chunk.write_u16::<BigEndian>(self.begin_loop).unwrap();
+        chunk.write_u16::<BigEndian>(self.end_loop).unwrap();
+    }
+}
+
+impl AiffInstrumentChunk {
+    pub fn from_chunk(chunk: &AiffChunk) -> Result<Self, WavTagError> {
+        chunk.check_tag(*b"INST")?;
+
+        Self::read_body(&chunk.data).map_err(|e| chunk.body_error(e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+
+        Ok(AiffInstrumentChunk {
+            base_note: data.read_u8()?,
+            detune: data.read_i8()?,
+            low_note: data.read_u8()?,
+            high_note: data.read_u8()?,
+            low_velocity: data.read_u8()?,
+            high_velocity: data.read_u8()?,
+            gain: data.read_i16::<BigEndian>()?,
+            sustain_loop: AiffLoop::read(&mut data)?,
+            release_loop: AiffLoop::read(&mut data)?,
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = vec![
+            self.base_note,
+            self.detune as u8,
+            self.low_note,
+            self.high_note,
+            self.low_velocity,
+            self.high_velocity,
+        ];
+
+        // writes to a vec are infallible.
+        chunk.write_i16::<BigEndian>(self.gain).unwrap();
+        self.sustain_loop.write(&mut chunk);
+        self.release_loop.write(&mut chunk);
+
+        chunk
+    }
+}
+
+impl AiffFile {
+    pub fn get_instrument_chunk(&self) -> Result<AiffInstrumentChunk, WavTagError> {
+        match self.find_chunk(*b"INST") {
+            Some(c) => AiffInstrumentChunk::from_chunk(c),
+            None => Ok(AiffInstrumentChunk::default()),
+        }
+    }
+
+    pub fn set_instrument_chunk(&mut self, chunk: AiffInstrumentChunk) {
+        self.add_or_replace_chunk(AiffChunk::new(*b"INST", chunk.serialise()));
+    }
+}
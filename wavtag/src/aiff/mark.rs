@@ -0,0 +1,85 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::Cursor;
+
+use crate::aiff::{read_pstring, write_pstring, AiffChunk, AiffFile};
+use crate::WavTagError;
+
+/// The marker chunk holds named positions in the sound data, which the instrument chunk's loops
+/// refer to by id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MarkerChunk {
+    pub markers: Vec<AiffMarker>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AiffMarker {
+    /// A unique id greater than 0.
+    pub id: u16,
+
+    /// The position of the marker in sample frames. Markers sit between frames, so a marker at
+    /// position 0 is before the first frame.
+    pub position: u32,
+
+    pub name: String,
+}
+
+impl MarkerChunk {
+    pub fn from_chunk(chunk: &AiffChunk) -> Result<Self, WavTagError> {
+        chunk.check_tag(*b"MARK")?;
+
+        Self::read_body(&chunk.data).map_err(|e| chunk.body_error(e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+        let num_markers = data.read_u16::<BigEndian>()?;
+
+        let markers = (0..num_markers)
+            .map(|_| {
+                Ok(AiffMarker {
+                    id: data.read_u16::<BigEndian>()?,
+                    position: data.read_u32::<BigEndian>()?,
+                    name: read_pstring(&mut data)?,
+                })
+            })
+            .collect::<Result<Vec<AiffMarker>, io::Error>>()?;
+
+        Ok(MarkerChunk { markers })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::new();
+
+        // writes to a vec are infallible.
+        chunk
+            .write_u16::<BigEndian>(self.markers.len() as u16)
+            .unwrap();
+
+        for marker in self.markers.iter() {
+            chunk.write_u16::<BigEndian>(marker.id).unwrap();
+            chunk.write_u32::<BigEndian>(marker.position).unwrap();
+            write_pstring(&mut chunk, &marker.name);
+        }
+
+        chunk
+    }
+
+    pub fn find_marker(&self, id: u16) -> Option<&AiffMarker> {
+        self.markers.iter().find(|m| m.id == id)
+    }
+}
+
+impl AiffFile {
+    pub fn get_marker_chunk(&self) -> Result<MarkerChunk, WavTagError> {
+        match self.find_chunk(*b"MARK") {
+            Some(c) => MarkerChunk::from_chunk(c),
+            None => Ok(MarkerChunk::default()),
+        }
+    }
+
+    pub fn set_marker_chunk(&mut self, chunk: MarkerChunk) {
+        self.add_or_replace_chunk(AiffChunk::new(*b"MARK", chunk.serialise()));
+    }
+}
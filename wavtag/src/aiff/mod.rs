@@ -0,0 +1,546 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+use crate::error::tag_name;
+use crate::{ChunkType, FormatTag, RiffChunk, RiffFile, RiffForm, WavTagError, WaveFormat};
+
+mod comm;
+pub use self::comm::CommonChunk;
+
+mod mark;
+pub use self::mark::{AiffMarker, MarkerChunk};
+
+mod comt;
+pub use self::comt::{AiffComment, CommentsChunk};
+
+mod inst;
+pub use self::inst::{AiffInstrumentChunk, AiffLoop, PlayMode};
+
+/// The two forms of an IFF audio file. AIFC adds compression types to the common chunk, which is
+/// also how little-endian and floating point samples are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AiffForm {
+    #[default]
+    Aiff,
+    Aifc,
+}
+
+/// A chunk of an AIFF file. Unlike RIFF, every field is big-endian.
+pub struct AiffChunk {
+    pub tag: [u8; 4],
+    pub data: Vec<u8>,
+
+    /// Where the chunk header was found in the file it was read from.
+    pub offset: Option<u64>,
+}
+
+impl AiffChunk {
+    pub fn new(tag: [u8; 4], data: Vec<u8>) -> Self {
+        AiffChunk {
+            tag,
+            data,
+            offset: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn chunk_type(&self) -> ChunkType {
+        ChunkType::Unknown(tag_name(&self.tag))
+    }
+
+    pub(crate) fn check_tag(&self, expected: [u8; 4]) -> Result<(), WavTagError> {
+        if self.tag != expected {
+            return Err(WavTagError::WrongChunkType {
+                expected: ChunkType::Unknown(tag_name(&expected)),
+                found: self.chunk_type(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn malformed(&self, reason: impl std::fmt::Display) -> WavTagError {
+        WavTagError::MalformedChunk {
+            chunk: self.chunk_type(),
+            offset: self.offset,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// See `WavTagError::from_body`.
+    pub(crate) fn body_error(&self, e: io::Error) -> WavTagError {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            self.malformed("chunk is too short")
+        } else {
+            self.malformed(e)
+        }
+    }
+}
+
+/// Read a Pascal string: a count byte followed by the text, padded so the whole is an even number
+/// of bytes.
+pub(crate) fn read_pstring<R: Read>(data: &mut R) -> Result<String, io::Error> {
+    let len = data.read_u8()? as usize;
+    let mut text = vec![0u8; len + (len + 1) % 2];
+    data.read_exact(&mut text)?;
+    text.truncate(len);
+    Ok(String::from_utf8_lossy(&text).into_owned())
+}
+
+/// Write a Pascal string, truncated to 255 bytes.
+pub(crate) fn write_pstring(data: &mut Vec<u8>, text: &str) {
+    let bytes = &text.as_bytes()[..text.len().min(255)];
+    data.push(bytes.len() as u8);
+    data.extend_from_slice(bytes);
+    if bytes.len().is_multiple_of(2) {
+        data.push(0);
+    }
+}
+
+/// Convert an 80-bit IEEE 754 extended precision float, used for the AIFF sample rate.
+pub(crate) fn read_extended<R: Read>(data: &mut R) -> Result<f64, io::Error> {
+    let sign_exponent = data.read_u16::<BigEndian>()?;
+    let mantissa = data.read_u64::<BigEndian>()?;
+
+    if mantissa == 0 {
+        return Ok(0.0);
+    }
+
+    // the mantissa has an explicit integer bit, so it is scaled by 2^63 on top of the bias.
+    let exponent = (sign_exponent & 0x7fff) as i32 - 16383 - 63;
+    let value = mantissa as f64 * 2f64.powi(exponent);
+
+    if sign_exponent & 0x8000 != 0 {
+        Ok(-value)
+    } else {
+        Ok(value)
+    }
+}
+
+/// Convert a float to 80-bit extended precision.
+pub(crate) fn write_extended(data: &mut Vec<u8>, value: f64) {
+    if value == 0.0 || !value.is_finite() {
+        data.extend_from_slice(&[0; 10]);
+        return;
+    }
+
+    let sign = if value < 0.0 { 0x8000 } else { 0 };
+    let value = value.abs();
+    let exponent = value.log2().floor() as i32;
+    let mantissa = (value * 2f64.powi(63 - exponent)) as u64;
+
+    // writes to a vec are infallible.
+    data.write_u16::<BigEndian>(sign | (exponent + 16383) as u16)
+        .unwrap();
+    data.write_u64::<BigEndian>(mantissa).unwrap();
+}
+
+/// The AIFC format version chunk holds the date of the AIFC spec the file follows.
+const AIFC_VERSION_1: u32 = 0xA280_5140;
+
+pub struct AiffFile {
+    pub filename: String,
+    pub chunks: Vec<AiffChunk>,
+    pub form: AiffForm,
+}
+
+impl AiffFile {
+    pub fn read<R: Read + Seek>(mut reader: R, filename: String) -> Result<Self, WavTagError> {
+        let stream_len = {
+            let start = reader.stream_position()?;
+            let end = reader.seek(SeekFrom::End(0))?;
+            reader.seek(SeekFrom::Start(start))?;
+            end
+        };
+
+        let read_tag = |reader: &mut R| -> Result<[u8; 4], WavTagError> {
+            let mut tag = [0u8; 4];
+            reader.read_exact(&mut tag)?;
+            Ok(tag)
+        };
+
+        let tag = read_tag(&mut reader)?;
+        if &tag != b"FORM" {
+            return Err(WavTagError::BadMagic {
+                offset: 0,
+                expected: "FORM",
+                found: tag,
+            });
+        }
+
+        let _ = reader.read_u32::<BigEndian>()?; // file length (minus FORM header).
+
+        let tag = read_tag(&mut reader)?;
+        let form = match &tag {
+            b"AIFF" => AiffForm::Aiff,
+            b"AIFC" => AiffForm::Aifc,
+            _ => {
+                return Err(WavTagError::BadMagic {
+                    offset: 8,
+                    expected: "AIFF or AIFC",
+                    found: tag,
+                })
+            }
+        };
+
+        let mut chunks = Vec::new();
+
+        loop {
+            let offset = reader.stream_position()?;
+            if offset >= stream_len {
+                break; // end of file found
+            }
+
+            if stream_len - offset < 8 {
+                break; // trailing bytes too short to be a chunk.
+            }
+
+            let tag = read_tag(&mut reader)?;
+            let chunk_len = reader.read_u32::<BigEndian>()? as u64;
+
+            // as with RIFF, a missing pad byte after the last chunk is tolerated. A chunk that
+            // runs past the end of the file is dropped, or cut down to the audio present if it's
+            // the sound data chunk, keeping the chunks before it.
+            let available = stream_len - offset - 8;
+            let chunk_len = if chunk_len <= available {
+                chunk_len
+            } else if &tag == b"SSND" {
+                available
+            } else {
+                break;
+            };
+
+            let data = crate::utils::read_bytes(&mut reader, chunk_len as usize)?;
+            if !chunk_len.is_multiple_of(2) && chunk_len < available {
+                reader.seek(SeekFrom::Current(1))?; // pad byte
+            }
+
+            let mut chunk = AiffChunk::new(tag, data);
+            chunk.offset = Some(offset);
+            chunks.push(chunk);
+        }
+
+        Ok(AiffFile {
+            filename,
+            chunks,
+            form,
+        })
+    }
+
+    /// The size of the file after the FORM header.
+    pub fn len(&self) -> usize {
+        4 + self
+            .chunks
+            .iter()
+            .fold(0, |acc, chunk| acc + chunk.len().div_ceil(2) * 2 + 8)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), WavTagError> {
+        writer.write_all(b"FORM")?;
+        writer.write_u32::<BigEndian>(self.len() as u32)?;
+        writer.write_all(match self.form {
+            AiffForm::Aiff => b"AIFF",
+            AiffForm::Aifc => b"AIFC",
+        })?;
+
+        for chunk in self.chunks.iter() {
+            writer.write_all(&chunk.tag)?;
+            writer.write_u32::<BigEndian>(chunk.len() as u32)?;
+            writer.write_all(&chunk.data)?;
+            if !chunk.len().is_multiple_of(2) {
+                writer.write_all(&[0])?; // pad byte
+            }
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn find_chunk(&self, tag: [u8; 4]) -> Option<&AiffChunk> {
+        self.chunks.iter().find(|c| c.tag == tag)
+    }
+
+    pub fn add_or_replace_chunk(&mut self, chunk: AiffChunk) {
+        match self.chunks.iter().position(|c| c.tag == chunk.tag) {
+            Some(index) => self.chunks[index] = chunk,
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    /// The raw sample data of the sound data chunk, after its offset field.
+    pub fn sound_data(&self) -> Result<&[u8], WavTagError> {
+        let chunk = self
+            .find_chunk(*b"SSND")
+            .ok_or_else(|| WavTagError::MissingChunk(ChunkType::Unknown("SSND".to_string())))?;
+
+        let offset = (&chunk.data[..])
+            .read_u32::<BigEndian>()
+            .map_err(|e| chunk.body_error(e))? as usize;
+
+        // the offset and block size fields come before the samples.
+        match chunk.data.get(8 + offset..) {
+            Some(data) => Ok(data),
+            None => Err(chunk.malformed("sample offset is past the end of the chunk")),
+        }
+    }
+
+    /// The audio converted to the little-endian layout of a WAV data chunk, along with the
+    /// matching format chunk.
+    pub fn wave_data(&self) -> Result<(WaveFormat, Vec<u8>), WavTagError> {
+        let common = self.common()?;
+        let data = self.sound_data()?;
+
+        // AIFF samples are left justified in whole bytes, as in WAV.
+        let container = common.sample_size.div_ceil(8) as usize;
+        if container == 0 {
+            return Err(WavTagError::InvalidValue {
+                field: "sample size",
+                reason: "0 bits per sample".to_string(),
+            });
+        }
+
+        let len = (common.sample_frames as usize * common.channels as usize * container)
+            .min(data.len() - data.len() % container);
+        let data = &data[..len];
+
+        let (format_tag, big_endian, signed) = match &common.compression_type {
+            b"NONE" | b"twos" => (FormatTag::Pcm, true, true),
+            b"sowt" => (FormatTag::Pcm, false, true),
+            b"raw " => (FormatTag::Pcm, false, false),
+            b"fl32" | b"FL32" | b"fl64" | b"FL64" => (FormatTag::IeeeFloat, true, true),
+            tag => {
+                return Err(WavTagError::Unsupported(format!(
+                    "unsupported AIFF-C compression type {:?}",
+                    tag_name(tag)
+                )))
+            }
+        };
+
+        let wave_data = match container {
+            // 8-bit WAV is unsigned.
+            1 if signed => data.iter().map(|b| b.wrapping_add(128)).collect(),
+            _ if big_endian => data
+                .chunks_exact(container)
+                .flat_map(|sample| sample.iter().rev())
+                .copied()
+                .collect(),
+            _ => data.to_vec(),
+        };
+
+        let format = WaveFormat::new(
+            format_tag,
+            common.channels,
+            common.sample_rate.round() as u32,
+            container as u16 * 8,
+        );
+
+        Ok((format, wave_data))
+    }
+}
+
+impl AiffFile {
+    /// Convert a WAV file to AIFF, carrying its cue points, loops, instrument and comments
+    /// across through `SampleMetadata`. Floating point samples need an AIFC file.
+    pub fn from_riff(riff: &RiffFile) -> Result<Self, WavTagError> {
+        let format = riff.format()?;
+        let data = match riff.find_chunk_by_type(ChunkType::Data) {
+            Some(c) => riff.read_chunk_data(c)?,
+            None => return Err(WavTagError::MissingChunk(ChunkType::Data)),
+        };
+
+        let container = (format.block_align / format.channels.max(1)) as usize;
+        let (form, compression_type, compression_name) = match (format.sample_format(), container) {
+            (FormatTag::Pcm, 1..=4) => (AiffForm::Aiff, *b"NONE", "not compressed"),
+            (FormatTag::IeeeFloat, 4) => (AiffForm::Aifc, *b"fl32", "32-bit floating point"),
+            (FormatTag::IeeeFloat, 8) => (AiffForm::Aifc, *b"fl64", "64-bit floating point"),
+            (tag, _) => {
+                return Err(WavTagError::Unsupported(format!(
+                    "can't write {:?} with {} byte samples to AIFF",
+                    tag, container
+                )))
+            }
+        };
+
+        let frames = format.frame_count(data.len());
+        let data = &data[..frames * format.block_align as usize];
+
+        let mut sound_data = Vec::with_capacity(8 + data.len());
+        sound_data.extend_from_slice(&[0; 8]); // offset and block size
+        match container {
+            // 8-bit AIFF is signed.
+            1 => sound_data.extend(data.iter().map(|b| b.wrapping_sub(128))),
+            _ => {
+                for sample in data.chunks_exact(container) {
+                    sound_data.extend(sample.iter().rev());
+                }
+            }
+        }
+
+        let mut file = AiffFile {
+            filename: riff.filename.clone(),
+            chunks: Vec::new(),
+            form,
+        };
+
+        if form == AiffForm::Aifc {
+            file.chunks.push(AiffChunk::new(
+                *b"FVER",
+                AIFC_VERSION_1.to_be_bytes().to_vec(),
+            ));
+        }
+        file.set_common(CommonChunk {
+            channels: format.channels,
+            sample_frames: frames as u32,
+            sample_size: match format.sample_format() {
+                FormatTag::Pcm => format.valid_bits_per_sample(),
+                _ => container as u16 * 8,
+            },
+            sample_rate: format.sample_rate as f64,
+            compression_type,
+            compression_name: compression_name.to_string(),
+        });
+        file.chunks.push(AiffChunk::new(*b"SSND", sound_data));
+        file.set_metadata(&riff.metadata()?);
+
+        Ok(file)
+    }
+}
+
+impl RiffFile {
+    /// Convert an AIFF file to WAV, carrying its markers, loops, instrument and comments across
+    /// through `SampleMetadata`.
+    pub fn from_aiff(aiff: &AiffFile) -> Result<Self, WavTagError> {
+        let (format, data) = aiff.wave_data()?;
+
+        let mut file = RiffFile {
+            filename: aiff.filename.clone(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(format);
+        file.chunks.push(RiffChunk::new(ChunkType::Data, data));
+        file.set_metadata(&aiff.metadata()?)?;
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const RATES: [(f64, [u8; 10]); 3] = [
+        (44100.0, [0x40, 0x0e, 0xac, 0x44, 0, 0, 0, 0, 0, 0]),
+        (48000.0, [0x40, 0x0e, 0xbb, 0x80, 0, 0, 0, 0, 0, 0]),
+        (22050.0, [0x40, 0x0d, 0xac, 0x44, 0, 0, 0, 0, 0, 0]),
+    ];
+
+    #[test]
+    fn reads_and_writes_extended_sample_rates() {
+        for (rate, bytes) in RATES {
+            assert_eq!(read_extended(&mut &bytes[..]).unwrap(), rate);
+
+            let mut out = Vec::new();
+            write_extended(&mut out, rate);
+            assert_eq!(out, bytes);
+        }
+    }
+
+    fn read_resource() -> AiffFile {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/smpl_cue.aif");
+        AiffFile::read(
+            std::fs::File::open(path).unwrap(),
+            "smpl_cue.aif".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn reads_markers_and_the_sustain_loop() {
+        let file = read_resource();
+
+        let common = file.common().unwrap();
+        assert_eq!(common.channels, 2);
+        assert_eq!(common.sample_rate, 44100.0);
+        assert_eq!(common.sample_size, 24);
+
+        let positions: Vec<(u16, u32)> = file
+            .get_marker_chunk()
+            .unwrap()
+            .markers
+            .iter()
+            .map(|m| (m.id, m.position))
+            .collect();
+        assert_eq!(
+            positions,
+            [(1, 142221), (2, 441000), (3, 176400), (4, 352800)]
+        );
+
+        let inst = file.get_instrument_chunk().unwrap();
+        assert_eq!(inst.base_note, 45);
+        assert_eq!(
+            inst.sustain_loop,
+            AiffLoop {
+                play_mode: PlayMode::Forward,
+                begin_loop: 3,
+                end_loop: 4,
+            }
+        );
+        assert_eq!(inst.release_loop.play_mode, PlayMode::NoLooping);
+
+        // the marker at the end of the loop is after the last frame played.
+        let loops = file.metadata().unwrap().loops;
+        assert_eq!(loops.len(), 1);
+        assert_eq!((loops[0].start, loops[0].end), (176400, 352799));
+    }
+
+    #[test]
+    fn round_trips_the_resource() {
+        let bytes = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../resources/smpl_cue.aif"
+        ))
+        .unwrap();
+        let mut out = Vec::new();
+        read_resource().write(&mut out).unwrap();
+        assert_eq!(out, bytes);
+    }
+
+    #[test]
+    fn drops_a_truncated_last_chunk() {
+        let mut bytes = b"FORM\0\0\0\0AIFF".to_vec();
+        bytes.extend_from_slice(b"COMT\0\0\0\x02\0\0");
+
+        let mut truncated = bytes.clone();
+        truncated.extend_from_slice(b"MARK\0\0\0\x64\0\0");
+        let file = AiffFile::read(Cursor::new(truncated), "test.aif".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 1);
+
+        let mut trailing = bytes.clone();
+        trailing.extend_from_slice(b"abc");
+        let file = AiffFile::read(Cursor::new(trailing), "test.aif".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 1);
+
+        // the audio that's there is kept.
+        let mut short_audio = bytes;
+        short_audio.extend_from_slice(b"SSND\0\0\0\x10\0\0\0\0\0\0\0\0\x01\x02");
+        let file = AiffFile::read(Cursor::new(short_audio), "test.aif".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 2);
+        assert_eq!(file.sound_data().unwrap(), [1, 2]);
+    }
+}
@@ -0,0 +1,191 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::Cursor;
+
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+const FLAG_ONE_SHOT: u32 = 0x01;
+const FLAG_ROOT_NOTE_SET: u32 = 0x02;
+const FLAG_STRETCH: u32 = 0x04;
+const FLAG_DISK_BASED: u32 = 0x08;
+const FLAG_HIGH_OCTAVE: u32 = 0x10;
+
+/// The acid chunk was introduced by Sonic Foundry ACID and is read by most loop browsers (eg.
+/// Ableton Live) to tempo-sync loops and transpose one-shots.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AcidChunk {
+    /// The file is a one-shot rather than a loop, and should not be tempo-synced.
+    pub one_shot: bool,
+
+    /// The root note field is valid and the file should follow the project key.
+    pub root_note_set: bool,
+
+    /// The file should be time stretched to the project tempo.
+    pub stretch: bool,
+
+    /// The file should be streamed from disk rather than loaded into memory.
+    pub disk_based: bool,
+
+    /// Some versions of ACID set this when the root note is an octave above the stored note.
+    pub high_octave: bool,
+
+    /// The MIDI note number of the root note (eg. 60 for C3).
+    pub root_note: u16,
+
+    /// Undocumented, usually 0x8000.
+    pub unknown1: u16,
+
+    /// Undocumented, usually 0.
+    pub unknown2: f32,
+
+    /// The number of beats in the loop.
+    pub beats: u32,
+
+    pub meter_denominator: u16,
+    pub meter_numerator: u16,
+
+    /// The original tempo of the loop in beats per minute.
+    pub tempo: f32,
+}
+
+impl Default for AcidChunk {
+    fn default() -> Self {
+        AcidChunk {
+            one_shot: false,
+            root_note_set: false,
+            stretch: false,
+            disk_based: false,
+            high_octave: false,
+            root_note: 60,
+            unknown1: 0x8000,
+            unknown2: 0.0,
+            beats: 0,
+            meter_denominator: 4,
+            meter_numerator: 4,
+            tempo: 120.0,
+        }
+    }
+}
+
+impl AcidChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Acid {
+            return Err(WavTagError::wrong_chunk(ChunkType::Acid, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+        let flags = data.read_u32::<LittleEndian>()?;
+
+        Ok(AcidChunk {
+            one_shot: flags & FLAG_ONE_SHOT != 0,
+            root_note_set: flags & FLAG_ROOT_NOTE_SET != 0,
+            stretch: flags & FLAG_STRETCH != 0,
+            disk_based: flags & FLAG_DISK_BASED != 0,
+            high_octave: flags & FLAG_HIGH_OCTAVE != 0,
+            root_note: data.read_u16::<LittleEndian>()?,
+            unknown1: data.read_u16::<LittleEndian>()?,
+            unknown2: data.read_f32::<LittleEndian>()?,
+            beats: data.read_u32::<LittleEndian>()?,
+            meter_denominator: data.read_u16::<LittleEndian>()?,
+            meter_numerator: data.read_u16::<LittleEndian>()?,
+            tempo: data.read_f32::<LittleEndian>()?,
+        })
+    }
+
+    pub fn flags(&self) -> u32 {
+        [
+            (self.one_shot, FLAG_ONE_SHOT),
+            (self.root_note_set, FLAG_ROOT_NOTE_SET),
+            (self.stretch, FLAG_STRETCH),
+            (self.disk_based, FLAG_DISK_BASED),
+            (self.high_octave, FLAG_HIGH_OCTAVE),
+        ]
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |acc, (_, flag)| acc | flag)
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(24);
+
+        // writes to a vec are infallible.
+        chunk.write_u32::<LittleEndian>(self.flags()).unwrap();
+        chunk.write_u16::<LittleEndian>(self.root_note).unwrap();
+        chunk.write_u16::<LittleEndian>(self.unknown1).unwrap();
+        chunk.write_f32::<LittleEndian>(self.unknown2).unwrap();
+        chunk.write_u32::<LittleEndian>(self.beats).unwrap();
+        chunk
+            .write_u16::<LittleEndian>(self.meter_denominator)
+            .unwrap();
+        chunk
+            .write_u16::<LittleEndian>(self.meter_numerator)
+            .unwrap();
+        chunk.write_f32::<LittleEndian>(self.tempo).unwrap();
+
+        chunk
+    }
+}
+
+impl RiffFile {
+    pub fn get_acid_chunk(&self) -> Result<AcidChunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Acid) {
+            Some(c) => AcidChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(AcidChunk::default()),
+        }
+    }
+
+    pub fn set_acid_chunk(&mut self, chunk: AcidChunk) {
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Acid, chunk.serialise()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_flags_and_tempo() {
+        let acid = AcidChunk {
+            one_shot: true,
+            stretch: true,
+            high_octave: true,
+            root_note: 57,
+            beats: 8,
+            meter_denominator: 8,
+            meter_numerator: 6,
+            tempo: 93.5,
+            ..AcidChunk::default()
+        };
+        assert_eq!(acid.flags(), 0x15);
+
+        let body = acid.serialise();
+        assert_eq!(body.len(), 24);
+        assert_eq!(body[..4], 0x15u32.to_le_bytes());
+        assert_eq!(body[4..8], [57, 0, 0x00, 0x80]);
+        assert_eq!(body[12..16], 8u32.to_le_bytes());
+        assert_eq!(body[16..20], [8, 0, 6, 0]);
+        assert_eq!(body[20..], 93.5f32.to_le_bytes());
+
+        let chunk = RiffChunk::new(ChunkType::Acid, body);
+        assert_eq!(AcidChunk::from_chunk(&chunk).unwrap(), acid);
+
+        // each flag is read on its own.
+        for flag in [
+            FLAG_ONE_SHOT,
+            FLAG_ROOT_NOTE_SET,
+            FLAG_STRETCH,
+            FLAG_DISK_BASED,
+            FLAG_HIGH_OCTAVE,
+        ] {
+            let mut body = AcidChunk::default().serialise();
+            body[..4].copy_from_slice(&flag.to_le_bytes());
+            let chunk = RiffChunk::new(ChunkType::Acid, body);
+            assert_eq!(AcidChunk::from_chunk(&chunk).unwrap().flags(), flag);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::chunks::list::{read_list, read_zstr, write_list, write_zstr, SubChunk};
+use crate::riff::header_to_rifftype;
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+const ADTL: [u8; 4] = *b"adtl";
+
+/// The associated data list is a LIST chunk of form type `adtl`. It attaches text to cue points
+/// (and, through the cue point ids, to sampler loops) such as "sustain loop" or "slice 3".
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AssociatedDataChunk {
+    pub entries: Vec<AssociatedData>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AssociatedData {
+    /// A `labl` sub-chunk, the title of a cue point.
+    Label { cue_point_id: u32, text: String },
+
+    /// A `note` sub-chunk, a comment attached to a cue point.
+    Note { cue_point_id: u32, text: String },
+
+    /// An `ltxt` sub-chunk, text attached to a region of samples starting at a cue point.
+    LabelledText(LabelledText),
+
+    /// Any other sub-chunk, kept as-is so it survives a round trip.
+    Unknown { tag: [u8; 4], data: Vec<u8> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LabelledText {
+    pub cue_point_id: u32,
+
+    /// The sample length specifies the number of samples in the segment of waveform data,
+    /// starting at the cue point.
+    pub sample_length: u32,
+
+    /// The purpose ID specifies what the text is used for. For example a value of `scrp` means
+    /// script text and `capt` means close-caption. Region markers written by most editors use `rgn `.
+    pub purpose_id: [u8; 4],
+
+    pub country: u16,
+    pub language: u16,
+    pub dialect: u16,
+    pub code_page: u16,
+    pub text: String,
+}
+
+impl Default for LabelledText {
+    fn default() -> Self {
+        LabelledText {
+            cue_point_id: 0,
+            sample_length: 0,
+            purpose_id: *b"rgn ",
+            country: 0,
+            language: 0,
+            dialect: 0,
+            code_page: 0,
+            text: String::new(),
+        }
+    }
+}
+
+impl AssociatedData {
+    pub fn cue_point_id(&self) -> Option<u32> {
+        match self {
+            AssociatedData::Label { cue_point_id, .. } => Some(*cue_point_id),
+            AssociatedData::Note { cue_point_id, .. } => Some(*cue_point_id),
+            AssociatedData::LabelledText(ltxt) => Some(ltxt.cue_point_id),
+            AssociatedData::Unknown { .. } => None,
+        }
+    }
+
+    fn from_sub_chunk(tag: [u8; 4], sub_chunk: Vec<u8>) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(&sub_chunk);
+
+        Ok(match header_to_rifftype(tag) {
+            ChunkType::Label => AssociatedData::Label {
+                cue_point_id: data.read_u32::<LittleEndian>()?,
+                text: read_zstr(&sub_chunk[4..]),
+            },
+            ChunkType::Note => AssociatedData::Note {
+                cue_point_id: data.read_u32::<LittleEndian>()?,
+                text: read_zstr(&sub_chunk[4..]),
+            },
+            ChunkType::LabelledText => AssociatedData::LabelledText(LabelledText {
+                cue_point_id: data.read_u32::<LittleEndian>()?,
+                sample_length: data.read_u32::<LittleEndian>()?,
+                purpose_id: {
+                    let mut purpose_id = [0u8; 4];
+                    data.read_exact(&mut purpose_id)?;
+                    purpose_id
+                },
+                country: data.read_u16::<LittleEndian>()?,
+                language: data.read_u16::<LittleEndian>()?,
+                dialect: data.read_u16::<LittleEndian>()?,
+                code_page: data.read_u16::<LittleEndian>()?,
+                text: read_zstr(&sub_chunk[20..]),
+            }),
+            _ => AssociatedData::Unknown {
+                tag,
+                data: sub_chunk,
+            },
+        })
+    }
+
+    fn to_sub_chunk(&self) -> SubChunk {
+        let mut data = Vec::new();
+
+        // writes to a vec are infallible.
+        match self {
+            AssociatedData::Label { cue_point_id, text } => {
+                data.write_u32::<LittleEndian>(*cue_point_id).unwrap();
+                write_zstr(&mut data, text);
+                (ChunkType::Label.to_tag(), data)
+            }
+            AssociatedData::Note { cue_point_id, text } => {
+                data.write_u32::<LittleEndian>(*cue_point_id).unwrap();
+                write_zstr(&mut data, text);
+                (ChunkType::Note.to_tag(), data)
+            }
+            AssociatedData::LabelledText(ltxt) => {
+                data.write_u32::<LittleEndian>(ltxt.cue_point_id).unwrap();
+                data.write_u32::<LittleEndian>(ltxt.sample_length).unwrap();
+                data.extend_from_slice(&ltxt.purpose_id);
+                data.write_u16::<LittleEndian>(ltxt.country).unwrap();
+                data.write_u16::<LittleEndian>(ltxt.language).unwrap();
+                data.write_u16::<LittleEndian>(ltxt.dialect).unwrap();
+                data.write_u16::<LittleEndian>(ltxt.code_page).unwrap();
+                if !ltxt.text.is_empty() {
+                    write_zstr(&mut data, &ltxt.text);
+                }
+                (ChunkType::LabelledText.to_tag(), data)
+            }
+            AssociatedData::Unknown { tag, data } => (*tag, data.clone()),
+        }
+    }
+}
+
+impl AssociatedDataChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        let (list_type, sub_chunks) = read_list(chunk)?;
+
+        if list_type != ADTL {
+            return Err(WavTagError::malformed(chunk, "not an adtl list"));
+        };
+
+        Ok(AssociatedDataChunk {
+            entries: sub_chunks
+                .into_iter()
+                .map(|(tag, sub_chunk)| AssociatedData::from_sub_chunk(tag, sub_chunk))
+                .collect::<Result<Vec<AssociatedData>, io::Error>>()
+                .map_err(|e| WavTagError::from_body(chunk, e))?,
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let sub_chunks: Vec<SubChunk> = self.entries.iter().map(|e| e.to_sub_chunk()).collect();
+        write_list(ADTL, &sub_chunks).data
+    }
+
+    /// The label (`labl`) for a cue point, if it has one.
+    pub fn label(&self, cue_point_id: u32) -> Option<&str> {
+        self.entries.iter().find_map(|e| match e {
+            AssociatedData::Label {
+                cue_point_id: id,
+                text,
+            } if *id == cue_point_id => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The note (`note`) for a cue point, if it has one.
+    pub fn note(&self, cue_point_id: u32) -> Option<&str> {
+        self.entries.iter().find_map(|e| match e {
+            AssociatedData::Note {
+                cue_point_id: id,
+                text,
+            } if *id == cue_point_id => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The labelled text (`ltxt`) for a cue point, if it has one.
+    pub fn labelled_text(&self, cue_point_id: u32) -> Option<&LabelledText> {
+        self.entries.iter().find_map(|e| match e {
+            AssociatedData::LabelledText(ltxt) if ltxt.cue_point_id == cue_point_id => Some(ltxt),
+            _ => None,
+        })
+    }
+
+    /// Set the label of a cue point, replacing any existing label for it.
+    pub fn set_label(&mut self, cue_point_id: u32, text: &str) {
+        self.entries.retain(
+            |e| !matches!(e, AssociatedData::Label { cue_point_id: id, .. } if *id == cue_point_id),
+        );
+        self.entries.push(AssociatedData::Label {
+            cue_point_id,
+            text: text.to_string(),
+        });
+    }
+
+    /// Set the note of a cue point, replacing any existing note for it.
+    pub fn set_note(&mut self, cue_point_id: u32, text: &str) {
+        self.entries.retain(
+            |e| !matches!(e, AssociatedData::Note { cue_point_id: id, .. } if *id == cue_point_id),
+        );
+        self.entries.push(AssociatedData::Note {
+            cue_point_id,
+            text: text.to_string(),
+        });
+    }
+
+    /// Set the labelled text of a cue point, replacing any existing labelled text for it.
+    pub fn set_labelled_text(&mut self, ltxt: LabelledText) {
+        self.entries.retain(|e| {
+            !matches!(e, AssociatedData::LabelledText(l) if l.cue_point_id == ltxt.cue_point_id)
+        });
+        self.entries.push(AssociatedData::LabelledText(ltxt));
+    }
+
+    /// Remove every entry attached to a cue point.
+    pub fn remove(&mut self, cue_point_id: u32) {
+        self.entries
+            .retain(|e| e.cue_point_id() != Some(cue_point_id));
+    }
+}
+
+impl RiffFile {
+    pub fn get_associated_data_chunk(&self) -> Result<AssociatedDataChunk, WavTagError> {
+        match self.find_list_chunk_by_type(ADTL) {
+            Some(c) => AssociatedDataChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(AssociatedDataChunk::default()),
+        }
+    }
+
+    pub fn set_associated_data_chunk(&mut self, chunk: AssociatedDataChunk) {
+        self.add_or_replace_list_chunk_by_type(
+            ADTL,
+            RiffChunk::new(ChunkType::List, chunk.serialise()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InfoChunk, RiffForm};
+
+    #[test]
+    fn round_trips_associated_data() {
+        let adtl = AssociatedDataChunk {
+            entries: vec![
+                AssociatedData::Label {
+                    cue_point_id: 1,
+                    text: "Loop".to_string(),
+                },
+                AssociatedData::Note {
+                    cue_point_id: 1,
+                    text: "Sustain".to_string(),
+                },
+                AssociatedData::LabelledText(LabelledText {
+                    cue_point_id: 2,
+                    sample_length: 4410,
+                    text: "Region".to_string(),
+                    ..LabelledText::default()
+                }),
+                AssociatedData::Unknown {
+                    tag: *b"file",
+                    data: vec![1, 2, 3],
+                },
+            ],
+        };
+
+        let body = adtl.serialise();
+        assert_eq!(&body[..8], b"adtllabl");
+        // the label's cue point id and "Loop\0" make an odd size, so a pad byte follows.
+        assert_eq!(body[8..12], 9u32.to_le_bytes());
+        assert_eq!(body[21], 0);
+        assert_eq!(&body[22..26], b"note");
+
+        let chunk = RiffChunk::new(ChunkType::List, body);
+        let read = AssociatedDataChunk::from_chunk(&chunk).unwrap();
+        assert_eq!(read, adtl);
+        assert_eq!(read.label(1), Some("Loop"));
+        assert_eq!(read.note(1), Some("Sustain"));
+        assert_eq!(read.labelled_text(2).unwrap().purpose_id, *b"rgn ");
+    }
+
+    #[test]
+    fn keeps_other_lists() {
+        let mut file = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        let mut info = InfoChunk::default();
+        info.set_comment("kept");
+        file.set_info_chunk(info);
+
+        let mut adtl = AssociatedDataChunk::default();
+        adtl.set_label(1, "first");
+        file.set_associated_data_chunk(adtl.clone());
+        adtl.set_label(1, "second");
+        file.set_associated_data_chunk(adtl);
+
+        assert_eq!(file.chunks.len(), 2);
+        assert_eq!(
+            file.get_associated_data_chunk().unwrap().label(1),
+            Some("second")
+        );
+        assert_eq!(file.get_info_chunk().unwrap().comment(), Some("kept"));
+    }
+}
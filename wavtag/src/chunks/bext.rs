@@ -0,0 +1,249 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Error, Read};
+
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+/// The size of the fixed fields, everything before the coding history.
+const BEXT_FIXED_LEN: usize = 602;
+
+/// The Broadcast Wave Format extension chunk (EBU Tech 3285). Field recorders and sound effects
+/// libraries use it for the description, originator and time stamp of a recording.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BextChunk {
+    /// A free text description of the sound (up to 256 characters).
+    pub description: String,
+
+    /// The name of the originator or the device that created the file (up to 32 characters).
+    pub originator: String,
+
+    /// An unambiguous reference allocated by the originator (up to 32 characters).
+    pub originator_reference: String,
+
+    /// The date of creation, as yyyy-mm-dd.
+    pub origination_date: String,
+
+    /// The time of creation, as hh:mm:ss.
+    pub origination_time: String,
+
+    /// The number of samples since midnight of the first sample in the file.
+    pub time_reference: u64,
+
+    /// 0, 1 (adds the UMID) or 2 (adds the loudness values).
+    pub version: u16,
+
+    /// The SMPTE 330M Unique Material Identifier. Version 1 and later.
+    pub umid: [u8; 64],
+
+    /// Integrated loudness in LUFS, multiplied by 100. Version 2 and later, as are the other
+    /// loudness fields.
+    pub loudness_value: i16,
+
+    /// Loudness range in LU, multiplied by 100.
+    pub loudness_range: i16,
+
+    /// Maximum true peak level in dBTP, multiplied by 100.
+    pub max_true_peak_level: i16,
+
+    /// Highest momentary loudness in LUFS, multiplied by 100.
+    pub max_momentary_loudness: i16,
+
+    /// Highest short term loudness in LUFS, multiplied by 100.
+    pub max_short_term_loudness: i16,
+
+    /// Lines of text describing the coding processes applied to the audio, each ending in CR/LF.
+    pub coding_history: String,
+}
+
+impl Default for BextChunk {
+    fn default() -> Self {
+        BextChunk {
+            description: String::new(),
+            originator: String::new(),
+            originator_reference: String::new(),
+            origination_date: String::new(),
+            origination_time: String::new(),
+            time_reference: 0,
+            version: 1,
+            umid: [0; 64],
+            loudness_value: 0,
+            loudness_range: 0,
+            max_true_peak_level: 0,
+            max_momentary_loudness: 0,
+            max_short_term_loudness: 0,
+            coding_history: String::new(),
+        }
+    }
+}
+
+fn read_fixed_str(data: &mut Cursor<&[u8]>, len: usize) -> Result<String, io::Error> {
+    let mut buf = vec![0u8; len];
+    data.read_exact(&mut buf)?;
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(len);
+    Ok(String::from_utf8_lossy(&buf[..end]).into_owned())
+}
+
+/// Write a string into a fixed width field, truncating it or padding it with nulls. Text is cut
+/// between characters so the field stays valid UTF-8.
+fn write_fixed_str(chunk: &mut Vec<u8>, text: &str, len: usize) {
+    let n = text
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .take_while(|&end| end <= len)
+        .last()
+        .unwrap_or(0);
+    chunk.extend_from_slice(&text.as_bytes()[..n]);
+    chunk.resize(chunk.len() + len - n, 0);
+}
+
+impl BextChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Broadcast {
+            return Err(WavTagError::wrong_chunk(ChunkType::Broadcast, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        if body.len() < BEXT_FIXED_LEN {
+            return Err(Error::other("chunk is too short for the fixed fields"));
+        }
+
+        let mut data = Cursor::new(body);
+
+        Ok(BextChunk {
+            description: read_fixed_str(&mut data, 256)?,
+            originator: read_fixed_str(&mut data, 32)?,
+            originator_reference: read_fixed_str(&mut data, 32)?,
+            origination_date: read_fixed_str(&mut data, 10)?,
+            origination_time: read_fixed_str(&mut data, 8)?,
+            time_reference: data.read_u64::<LittleEndian>()?,
+            version: data.read_u16::<LittleEndian>()?,
+            umid: {
+                let mut umid = [0u8; 64];
+                data.read_exact(&mut umid)?;
+                umid
+            },
+            loudness_value: data.read_i16::<LittleEndian>()?,
+            loudness_range: data.read_i16::<LittleEndian>()?,
+            max_true_peak_level: data.read_i16::<LittleEndian>()?,
+            max_momentary_loudness: data.read_i16::<LittleEndian>()?,
+            max_short_term_loudness: data.read_i16::<LittleEndian>()?,
+            coding_history: {
+                // skip the reserved bytes.
+                data.set_position(BEXT_FIXED_LEN as u64);
+                let mut coding_history = Vec::new();
+                data.read_to_end(&mut coding_history)?;
+                let end = coding_history
+                    .iter()
+                    .position(|&b| b == 0)
+                    .unwrap_or(coding_history.len());
+                String::from_utf8_lossy(&coding_history[..end]).into_owned()
+            },
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(BEXT_FIXED_LEN + self.coding_history.len());
+
+        write_fixed_str(&mut chunk, &self.description, 256);
+        write_fixed_str(&mut chunk, &self.originator, 32);
+        write_fixed_str(&mut chunk, &self.originator_reference, 32);
+        write_fixed_str(&mut chunk, &self.origination_date, 10);
+        write_fixed_str(&mut chunk, &self.origination_time, 8);
+
+        // writes to a vec are infallible.
+        chunk
+            .write_u64::<LittleEndian>(self.time_reference)
+            .unwrap();
+        chunk.write_u16::<LittleEndian>(self.version).unwrap();
+        chunk.extend_from_slice(&self.umid);
+        for value in [
+            self.loudness_value,
+            self.loudness_range,
+            self.max_true_peak_level,
+            self.max_momentary_loudness,
+            self.max_short_term_loudness,
+        ] {
+            chunk.write_i16::<LittleEndian>(value).unwrap();
+        }
+        chunk.resize(BEXT_FIXED_LEN, 0); // reserved
+
+        chunk.extend_from_slice(self.coding_history.as_bytes());
+
+        chunk
+    }
+}
+
+impl RiffFile {
+    pub fn get_bext_chunk(&self) -> Result<BextChunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Broadcast) {
+            Some(c) => BextChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(BextChunk::default()),
+        }
+    }
+
+    pub fn set_bext_chunk(&mut self, chunk: BextChunk) {
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Broadcast, chunk.serialise()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_the_fixed_fields_and_coding_history() {
+        let bext = BextChunk {
+            description: "Door slam, close".to_string(),
+            originator: "Recorder".to_string(),
+            originator_reference: "REF0001".to_string(),
+            origination_date: "2024-05-01".to_string(),
+            origination_time: "12:30:00".to_string(),
+            time_reference: 0x1_0000_0001,
+            version: 2,
+            umid: [7; 64],
+            loudness_value: -2300,
+            loudness_range: 450,
+            max_true_peak_level: -100,
+            max_momentary_loudness: -1800,
+            max_short_term_loudness: -2000,
+            coding_history: "A=PCM,F=48000,W=24,M=stereo\r\n".to_string(),
+        };
+
+        let body = bext.serialise();
+        assert_eq!(body.len(), 602 + bext.coding_history.len());
+        assert_eq!(&body[256..264], b"Recorder");
+        assert_eq!(&body[320..330], b"2024-05-01");
+        assert_eq!(body[338..346], 0x1_0000_0001u64.to_le_bytes());
+        assert_eq!(body[346..348], 2u16.to_le_bytes());
+        assert_eq!(body[412..414], (-2300i16).to_le_bytes());
+        // the reserved bytes.
+        assert!(body[422..602].iter().all(|&b| b == 0));
+
+        let chunk = RiffChunk::new(ChunkType::Broadcast, body);
+        assert_eq!(BextChunk::from_chunk(&chunk).unwrap(), bext);
+
+        let chunk = RiffChunk::new(ChunkType::Broadcast, vec![0; 601]);
+        assert!(BextChunk::from_chunk(&chunk).is_err());
+    }
+
+    #[test]
+    fn truncates_text_between_characters() {
+        let bext = BextChunk {
+            description: format!("{}é", "a".repeat(255)),
+            originator: "ü".repeat(20),
+            ..BextChunk::default()
+        };
+
+        let body = bext.serialise();
+        assert_eq!(body.len(), 602);
+        assert_eq!(body[255], 0);
+
+        let read = BextChunk::from_chunk(&RiffChunk::new(ChunkType::Broadcast, body)).unwrap();
+        assert_eq!(read.description, "a".repeat(255));
+        assert_eq!(read.originator, "ü".repeat(16));
+    }
+}
@@ -0,0 +1,204 @@
+use std::io;
+
+use crate::{
+    AudioData, ChunkType, FormatTag, RiffChunk, RiffFile, RiffForm, Samples, WavTagError,
+    WaveFormat,
+};
+
+/// The clm chunk marks a file as a wavetable, as written by Xfer Serum and read by most wavetable
+/// synths. It holds a short line of text such as `<!>2048 01000000 wavetable (www.xferrecords.com)`
+/// giving the number of sample frames in each frame of the table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WavetableChunk {
+    /// The number of sample frames in each wavetable frame (eg. 2048).
+    pub frame_size: u32,
+
+    /// The rest of the text after the frame size. Serum stores undocumented flags here, followed
+    /// by the name of the software that wrote the file.
+    pub info: String,
+}
+
+impl Default for WavetableChunk {
+    fn default() -> Self {
+        WavetableChunk {
+            frame_size: 2048,
+            info: "00000000 wavetable (wavtag)".to_string(),
+        }
+    }
+}
+
+impl WavetableChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Wavetable {
+            return Err(WavTagError::wrong_chunk(ChunkType::Wavetable, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let invalid = |reason: &str| io::Error::other(reason.to_string());
+
+        let text = String::from_utf8_lossy(body);
+        let text = match text.trim_end_matches('\0').strip_prefix("<!>") {
+            Some(text) => text.to_string(),
+            None => return Err(invalid("expected a \"<!>\" marker")),
+        };
+
+        let (frame_size, info) = text.split_once(' ').unwrap_or((&text, ""));
+        let frame_size = frame_size
+            .trim()
+            .parse()
+            .map_err(|_| invalid("the frame size isn't a number"))?;
+
+        Ok(WavetableChunk {
+            frame_size,
+            info: info.to_string(),
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        format!("<!>{} {}", self.frame_size, self.info).into_bytes()
+    }
+
+    /// The number of whole wavetable frames in `sample_frames` of audio.
+    pub fn frame_count(&self, sample_frames: usize) -> usize {
+        if self.frame_size == 0 {
+            return 0;
+        }
+        sample_frames / self.frame_size as usize
+    }
+}
+
+/// Mix a cycle down to mono and stretch it to `frame_size` sample frames. The cycle repeats, so
+/// the last sample is interpolated towards the first.
+fn resample_cycle(cycle: &AudioData, frame_size: usize) -> Vec<f32> {
+    let channels = cycle.channels.max(1) as usize;
+    let mono: Vec<f32> = cycle
+        .samples
+        .to_f32()
+        .chunks_exact(channels)
+        .map(|frame| frame.iter().sum::<f32>() / channels as f32)
+        .collect();
+
+    if mono.is_empty() {
+        return vec![0.0; frame_size];
+    }
+
+    let step = mono.len() as f64 / frame_size as f64;
+    (0..frame_size)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let a = mono[index % mono.len()];
+            let b = mono[(index + 1) % mono.len()];
+            a + (b - a) * fraction
+        })
+        .collect()
+}
+
+impl RiffFile {
+    pub fn get_wavetable_chunk(&self) -> Result<WavetableChunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Wavetable) {
+            Some(c) => WavetableChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(WavetableChunk::default()),
+        }
+    }
+
+    pub fn set_wavetable_chunk(&mut self, chunk: WavetableChunk) {
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Wavetable, chunk.serialise()));
+    }
+
+    /// Build a mono 32-bit float wavetable from single-cycle waveforms, one frame per cycle in
+    /// order. Each cycle is mixed to mono and resampled to the frame size of the clm chunk.
+    pub fn from_cycles(
+        filename: String,
+        cycles: &[AudioData],
+        sample_rate: u32,
+        clm: WavetableChunk,
+    ) -> Result<Self, WavTagError> {
+        if clm.frame_size == 0 {
+            return Err(WavTagError::InvalidValue {
+                field: "frame size",
+                reason: "must be at least one sample frame".to_string(),
+            });
+        }
+        if cycles.is_empty() {
+            return Err(WavTagError::InvalidValue {
+                field: "cycles",
+                reason: "a wavetable needs at least one cycle".to_string(),
+            });
+        }
+
+        let samples = cycles
+            .iter()
+            .flat_map(|cycle| resample_cycle(cycle, clm.frame_size as usize))
+            .collect();
+        let audio = AudioData {
+            channels: 1,
+            samples: Samples::F32(samples),
+        };
+
+        let mut file = RiffFile {
+            filename,
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(FormatTag::IeeeFloat, 1, sample_rate, 32));
+        file.set_wavetable_chunk(clm);
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, audio.encode()));
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_writes_serum_text() {
+        let body = b"<!>2048 01000000 wavetable (www.xferrecords.com)\0";
+        let chunk = RiffChunk::new(ChunkType::Wavetable, body.to_vec());
+        let clm = WavetableChunk::from_chunk(&chunk).unwrap();
+        assert_eq!(clm.frame_size, 2048);
+        assert_eq!(clm.info, "01000000 wavetable (www.xferrecords.com)");
+        assert_eq!(clm.serialise(), body[..body.len() - 1]);
+
+        let chunk = RiffChunk::new(ChunkType::Wavetable, b"2048 wavetable".to_vec());
+        assert!(WavetableChunk::from_chunk(&chunk).is_err());
+    }
+
+    #[test]
+    fn builds_a_wavetable_from_cycles() {
+        let cycle = |samples: Vec<i16>| AudioData {
+            channels: 2,
+            samples: Samples::I16(samples),
+        };
+        let cycles = [
+            cycle(vec![0; 64]),
+            cycle(vec![1000; 200]),
+            cycle(vec![-1; 2]),
+        ];
+        let clm = WavetableChunk {
+            frame_size: 256,
+            ..WavetableChunk::default()
+        };
+
+        let file = RiffFile::from_cycles("table.wav".to_string(), &cycles, 48000, clm).unwrap();
+        let format = file.format().unwrap();
+        assert_eq!(format, WaveFormat::new(FormatTag::IeeeFloat, 1, 48000, 32));
+        assert_eq!(format.sample_format(), FormatTag::IeeeFloat);
+
+        let clm = file.get_wavetable_chunk().unwrap();
+        assert_eq!(clm.frame_size, 256);
+        let data = file.find_chunk_by_type(ChunkType::Data).unwrap();
+        assert_eq!(format.frame_count(data.len()), 3 * 256);
+        assert_eq!(clm.frame_count(format.frame_count(data.len())), 3);
+
+        assert!(RiffFile::from_cycles("table.wav".to_string(), &[], 48000, clm).is_err());
+    }
+}
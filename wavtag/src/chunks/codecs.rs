@@ -0,0 +1,190 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+/// How far the IMA ADPCM step index moves for each code.
+const IMA_INDEX_TABLE: [i32; 16] = [-1, -1, -1, -1, 2, 4, 6, 8, -1, -1, -1, -1, 2, 4, 6, 8];
+
+const IMA_STEP_TABLE: [i32; 89] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31, 34, 37, 41, 45, 50, 55, 60, 66,
+    73, 80, 88, 97, 107, 118, 130, 143, 157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449,
+    494, 544, 598, 658, 724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272,
+    2499, 2749, 3024, 3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493,
+    10442, 11487, 12635, 13899, 15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// ITU G.711 µ-law, as used by telephony and some early sample CDs.
+pub(crate) fn decode_mu_law(byte: u8) -> i16 {
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0f) as i32;
+    let magnitude = (((mantissa << 3) + 0x84) << exponent) - 0x84;
+
+    if byte & 0x80 != 0 {
+        -magnitude as i16
+    } else {
+        magnitude as i16
+    }
+}
+
+/// ITU G.711 A-law. Unlike µ-law, a set sign bit means the sample is positive.
+pub(crate) fn decode_a_law(byte: u8) -> i16 {
+    let byte = byte ^ 0x55;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0f) as i32;
+    let magnitude = match exponent {
+        0 => (mantissa << 4) + 8,
+        _ => ((mantissa << 4) + 0x108) << (exponent - 1),
+    };
+
+    if byte & 0x80 != 0 {
+        magnitude as i16
+    } else {
+        -magnitude as i16
+    }
+}
+
+/// The predictor of one channel of an IMA ADPCM block.
+struct ImaState {
+    predictor: i32,
+    index: usize,
+}
+
+impl ImaState {
+    fn decode(&mut self, code: u8) -> i16 {
+        let step = IMA_STEP_TABLE[self.index];
+
+        let mut diff = step >> 3;
+        if code & 4 != 0 {
+            diff += step;
+        }
+        if code & 2 != 0 {
+            diff += step >> 1;
+        }
+        if code & 1 != 0 {
+            diff += step >> 2;
+        }
+
+        self.predictor = match code & 8 {
+            0 => self.predictor + diff,
+            _ => self.predictor - diff,
+        }
+        .clamp(i16::MIN as i32, i16::MAX as i32);
+        self.index = (self.index as i32 + IMA_INDEX_TABLE[code as usize]).clamp(0, 88) as usize;
+
+        self.predictor as i16
+    }
+}
+
+/// The number of sample frames in an IMA ADPCM block of `len` bytes, which is less than
+/// `samples_per_block` for a short final block. Each block starts with a 4 byte header per
+/// channel holding the first sample, followed by groups of 4 bytes (8 samples) per channel.
+pub(crate) fn ima_adpcm_block_frames(
+    len: usize,
+    channels: usize,
+    samples_per_block: usize,
+) -> usize {
+    let header = 4 * channels;
+    if channels == 0 || len < header {
+        return 0;
+    }
+
+    (1 + (len - header) / header * 8).min(samples_per_block)
+}
+
+/// Decode Microsoft (DVI) IMA ADPCM with 4 bits per sample to interleaved 16-bit samples.
+pub(crate) fn decode_ima_adpcm(
+    data: &[u8],
+    channels: usize,
+    block_align: usize,
+    samples_per_block: usize,
+) -> Vec<i16> {
+    let mut samples = Vec::new();
+    if channels == 0 || block_align == 0 {
+        return samples;
+    }
+
+    for block in data.chunks(block_align) {
+        let frames = ima_adpcm_block_frames(block.len(), channels, samples_per_block);
+        if frames == 0 {
+            break;
+        }
+
+        let mut out = vec![0i16; frames * channels];
+        let mut states: Vec<ImaState> = block[..4 * channels]
+            .chunks_exact(4)
+            .enumerate()
+            .map(|(channel, header)| {
+                let predictor = LittleEndian::read_i16(&header[0..2]);
+                out[channel] = predictor;
+                ImaState {
+                    predictor: predictor as i32,
+                    index: (header[2] as usize).min(88),
+                }
+            })
+            .collect();
+
+        let body = &block[4 * channels..];
+        for (group, bytes) in body.chunks_exact(4 * channels).enumerate() {
+            for (channel, state) in states.iter_mut().enumerate() {
+                let codes = bytes[channel * 4..channel * 4 + 4]
+                    .iter()
+                    .flat_map(|&b| [b & 0x0f, b >> 4]);
+                for (i, code) in codes.enumerate() {
+                    let frame = 1 + group * 8 + i;
+                    if frame < frames {
+                        out[frame * channels + channel] = state.decode(code);
+                    }
+                }
+            }
+        }
+
+        samples.extend_from_slice(&out);
+    }
+
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_g711() {
+        assert_eq!(decode_mu_law(0x00), -32124);
+        assert_eq!(decode_mu_law(0x80), 32124);
+        assert_eq!(decode_mu_law(0xff), 0);
+        assert_eq!(decode_a_law(0xd5), 8);
+        assert_eq!(decode_a_law(0x55), -8);
+        assert_eq!(decode_a_law(0xaa), 32256);
+    }
+
+    #[test]
+    fn decodes_an_ima_adpcm_block() {
+        // a mono block: the header sample, then codes 7, 7, 8, 0, 0, 0, 0, 0 low nibble first.
+        let block = [0, 0, 0, 0, 0x77, 0x08, 0x00, 0x00];
+        assert_eq!(
+            decode_ima_adpcm(&block, 1, 8, 9),
+            [0, 11, 41, 37, 40, 43, 46, 48, 50]
+        );
+    }
+
+    #[test]
+    fn decodes_ima_adpcm_channels_in_turn() {
+        // the left channel as above, the right starting at 1000 with codes that don't move it.
+        let block = [0, 0, 0, 0, 0xe8, 0x03, 0, 0, 0x77, 0x08, 0, 0, 0, 0, 0, 0];
+        let samples = decode_ima_adpcm(&block, 2, 16, 9);
+
+        let left: Vec<i16> = samples.iter().step_by(2).copied().collect();
+        assert_eq!(left, [0, 11, 41, 37, 40, 43, 46, 48, 50]);
+        assert!(samples.iter().skip(1).step_by(2).all(|&s| s == 1000));
+    }
+
+    #[test]
+    fn counts_the_frames_of_a_short_last_block() {
+        assert_eq!(ima_adpcm_block_frames(256, 1, 505), 505);
+        assert_eq!(ima_adpcm_block_frames(12, 1, 505), 17);
+        assert_eq!(ima_adpcm_block_frames(3, 1, 505), 0);
+
+        let blocks = [[0, 0, 0, 0, 0x77, 0x08, 0, 0].as_slice(), &[0x10, 0, 0, 0]].concat();
+        assert_eq!(decode_ima_adpcm(&blocks, 1, 8, 9).len(), 10);
+    }
+}
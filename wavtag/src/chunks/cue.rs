@@ -0,0 +1,171 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CueChunk {
+    pub cue_points: Vec<CuePoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CuePoint {
+    /// The ID is a unique identifier for the cue point. It is referenced by sample loops in the
+    /// sampler chunk and by labels, notes and labelled text in the associated data list chunk.
+    pub id: u32,
+
+    /// The position specifies the sample offset associated with the cue point in terms of the
+    /// sample's position in the final stream of samples generated by the play list. If no play
+    /// list chunk is present, this is equal to the sample offset.
+    pub position: u32,
+
+    /// The data chunk ID specifies the four character tag of the chunk containing the cue point.
+    /// This is `data` for regular files, or `slnt` if the cue point sits in a silence chunk of a
+    /// wave list.
+    pub data_chunk_id: [u8; 4],
+
+    /// The chunk start is the byte offset of the start of the chunk containing the cue point
+    /// within a wave list. It is 0 when there is no wave list chunk.
+    pub chunk_start: u32,
+
+    /// The block start is the byte offset of the first block containing the cue point. For
+    /// uncompressed PCM data this is 0 (or equal to the chunk start).
+    pub block_start: u32,
+
+    /// The sample offset specifies the offset in samples of the cue point within the block
+    /// identified by the block start.
+    pub sample_offset: u32,
+}
+
+impl Default for CuePoint {
+    fn default() -> Self {
+        CuePoint {
+            id: 0,
+            position: 0,
+            data_chunk_id: *b"data",
+            chunk_start: 0,
+            block_start: 0,
+            sample_offset: 0,
+        }
+    }
+}
+
+impl CueChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Cue {
+            return Err(WavTagError::wrong_chunk(ChunkType::Cue, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+        let num_cue_points = data.read_u32::<LittleEndian>()?;
+
+        let cue_points = (0..num_cue_points)
+            .map(|_| {
+                Ok(CuePoint {
+                    id: data.read_u32::<LittleEndian>()?,
+                    position: data.read_u32::<LittleEndian>()?,
+                    data_chunk_id: {
+                        let mut tag = [0u8; 4];
+                        data.read_exact(&mut tag)?;
+                        tag
+                    },
+                    chunk_start: data.read_u32::<LittleEndian>()?,
+                    block_start: data.read_u32::<LittleEndian>()?,
+                    sample_offset: data.read_u32::<LittleEndian>()?,
+                })
+            })
+            .collect::<Result<Vec<CuePoint>, io::Error>>()?;
+
+        Ok(CueChunk { cue_points })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(4 + self.cue_points.len() * 24);
+
+        // writes to a vec are infallible.
+        chunk
+            .write_u32::<LittleEndian>(self.cue_points.len() as u32)
+            .unwrap();
+
+        for cue_point in self.cue_points.iter() {
+            chunk.write_u32::<LittleEndian>(cue_point.id).unwrap();
+            chunk.write_u32::<LittleEndian>(cue_point.position).unwrap();
+            chunk.extend_from_slice(&cue_point.data_chunk_id);
+            chunk
+                .write_u32::<LittleEndian>(cue_point.chunk_start)
+                .unwrap();
+            chunk
+                .write_u32::<LittleEndian>(cue_point.block_start)
+                .unwrap();
+            chunk
+                .write_u32::<LittleEndian>(cue_point.sample_offset)
+                .unwrap();
+        }
+
+        chunk
+    }
+
+    /// Find a cue point by its unique id.
+    pub fn find_cue_point(&self, id: u32) -> Option<&CuePoint> {
+        self.cue_points.iter().find(|c| c.id == id)
+    }
+}
+
+impl RiffFile {
+    pub fn get_cue_chunk(&self) -> Result<CueChunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Cue) {
+            Some(c) => CueChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(CueChunk::default()),
+        }
+    }
+
+    pub fn set_cue_chunk(&mut self, chunk: CueChunk) {
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Cue, chunk.serialise()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_cue_points() {
+        let cue = CueChunk {
+            cue_points: vec![
+                CuePoint {
+                    id: 1,
+                    position: 100,
+                    sample_offset: 100,
+                    ..CuePoint::default()
+                },
+                CuePoint {
+                    id: 2,
+                    position: 4000,
+                    data_chunk_id: *b"slnt",
+                    chunk_start: 8,
+                    block_start: 16,
+                    sample_offset: 12,
+                },
+            ],
+        };
+
+        let body = cue.serialise();
+        assert_eq!(body.len(), 4 + 2 * 24);
+        assert_eq!(body[..4], 2u32.to_le_bytes());
+        assert_eq!(&body[36..40], b"slnt");
+
+        let chunk = RiffChunk::new(ChunkType::Cue, body);
+        assert_eq!(CueChunk::from_chunk(&chunk).unwrap(), cue);
+        assert_eq!(cue.find_cue_point(2), Some(&cue.cue_points[1]));
+
+        // a cue point count larger than the chunk is an error rather than a short list.
+        let chunk = RiffChunk::new(ChunkType::Cue, 3u32.to_le_bytes().to_vec());
+        assert!(CueChunk::from_chunk(&chunk).is_err());
+    }
+}
@@ -0,0 +1,493 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use crate::chunks::codecs::{decode_a_law, decode_ima_adpcm, decode_mu_law};
+use crate::{ChunkType, FormatTag, RiffChunk, RiffFile, WavTagError, WaveFormat};
+
+/// Samples in one of the sample formats a data chunk can hold. Multichannel audio is stored
+/// interleaved, frame by frame.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Samples {
+    /// 8-bit unsigned PCM, centred on 128.
+    U8(Vec<u8>),
+    I16(Vec<i16>),
+    /// 24-bit packed PCM, sign extended into the low 24 bits of each i32.
+    I24(Vec<i32>),
+    I32(Vec<i32>),
+    F32(Vec<f32>),
+    F64(Vec<f64>),
+}
+
+/// Decoded audio from a data chunk.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioData {
+    pub channels: u16,
+    /// Interleaved samples, `channels` per frame.
+    pub samples: Samples,
+}
+
+fn deinterleave<T: Copy>(samples: &[T], channels: usize) -> Vec<Vec<T>> {
+    (0..channels)
+        .map(|c| samples.iter().skip(c).step_by(channels).copied().collect())
+        .collect()
+}
+
+fn interleave<T: Copy>(channels: &[&Vec<T>]) -> Vec<T> {
+    let frames = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    (0..frames)
+        .flat_map(|f| channels.iter().map(move |c| c[f]))
+        .collect()
+}
+
+impl Samples {
+    pub fn len(&self) -> usize {
+        match self {
+            Samples::U8(s) => s.len(),
+            Samples::I16(s) => s.len(),
+            Samples::I24(s) => s.len(),
+            Samples::I32(s) => s.len(),
+            Samples::F32(s) => s.len(),
+            Samples::F64(s) => s.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The format tag and container size in bits needed to store these samples.
+    pub fn format(&self) -> (FormatTag, u16) {
+        match self {
+            Samples::U8(_) => (FormatTag::Pcm, 8),
+            Samples::I16(_) => (FormatTag::Pcm, 16),
+            Samples::I24(_) => (FormatTag::Pcm, 24),
+            Samples::I32(_) => (FormatTag::Pcm, 32),
+            Samples::F32(_) => (FormatTag::IeeeFloat, 32),
+            Samples::F64(_) => (FormatTag::IeeeFloat, 64),
+        }
+    }
+
+    /// Every sample scaled to the range -1.0..1.0, for analysis.
+    pub fn to_f32(&self) -> Vec<f32> {
+        match self {
+            Samples::U8(s) => s.iter().map(|&v| (v as f32 - 128.0) / 128.0).collect(),
+            Samples::I16(s) => s.iter().map(|&v| v as f32 / 32_768.0).collect(),
+            Samples::I24(s) => s.iter().map(|&v| v as f32 / 8_388_608.0).collect(),
+            Samples::I32(s) => s.iter().map(|&v| v as f32 / 2_147_483_648.0).collect(),
+            Samples::F32(s) => s.clone(),
+            Samples::F64(s) => s.iter().map(|&v| v as f32).collect(),
+        }
+    }
+}
+
+impl AudioData {
+    /// Decode the raw bytes of a data chunk as described by its format chunk.
+    pub fn decode(format: &WaveFormat, data: &[u8]) -> Result<Self, WavTagError> {
+        if format.channels == 0 || !format.block_align.is_multiple_of(format.channels) {
+            return Err(WavTagError::InvalidValue {
+                field: "block align",
+                reason: format!(
+                    "{} bytes doesn't divide into {} channels",
+                    format.block_align, format.channels
+                ),
+            });
+        }
+
+        if format.sample_format() == FormatTag::ImaAdpcm {
+            if format.bits_per_sample != 4 {
+                return Err(WavTagError::Unsupported(format!(
+                    "unsupported IMA ADPCM with {} bits per sample",
+                    format.bits_per_sample
+                )));
+            }
+
+            return Ok(AudioData {
+                channels: format.channels,
+                samples: Samples::I16(decode_ima_adpcm(
+                    data,
+                    format.channels as usize,
+                    format.block_align as usize,
+                    format.samples_per_block(),
+                )),
+            });
+        }
+
+        let container = (format.block_align / format.channels) as usize;
+
+        // ignore any trailing partial frame (eg. a pad byte).
+        let len = format.frame_count(data.len()) * format.block_align as usize;
+        let data = &data[..len];
+        let count = len / container;
+
+        let samples = match (format.sample_format(), container) {
+            (FormatTag::Pcm, 1) => Samples::U8(data.to_vec()),
+            (FormatTag::Pcm, 2) => {
+                let mut samples = vec![0; count];
+                LittleEndian::read_i16_into(data, &mut samples);
+                Samples::I16(samples)
+            }
+            (FormatTag::Pcm, 3) => {
+                Samples::I24(data.chunks_exact(3).map(LittleEndian::read_i24).collect())
+            }
+            (FormatTag::Pcm, 4) => {
+                let mut samples = vec![0; count];
+                LittleEndian::read_i32_into(data, &mut samples);
+                Samples::I32(samples)
+            }
+            (FormatTag::IeeeFloat, 4) => {
+                let mut samples = vec![0.0; count];
+                LittleEndian::read_f32_into(data, &mut samples);
+                Samples::F32(samples)
+            }
+            // G.711 is expanded to 16-bit so no precision is lost.
+            (FormatTag::MuLaw, 1) => Samples::I16(data.iter().map(|&b| decode_mu_law(b)).collect()),
+            (FormatTag::ALaw, 1) => Samples::I16(data.iter().map(|&b| decode_a_law(b)).collect()),
+            (FormatTag::IeeeFloat, 8) => {
+                let mut samples = vec![0.0; count];
+                LittleEndian::read_f64_into(data, &mut samples);
+                Samples::F64(samples)
+            }
+            (tag, _) => {
+                return Err(WavTagError::Unsupported(format!(
+                    "unsupported sample format {:?} with {} byte samples",
+                    tag, container
+                )))
+            }
+        };
+
+        Ok(AudioData {
+            channels: format.channels,
+            samples,
+        })
+    }
+
+    /// Encode the samples as the raw bytes of a data chunk.
+    pub fn encode(&self) -> Vec<u8> {
+        match &self.samples {
+            Samples::U8(s) => s.clone(),
+            Samples::I16(s) => {
+                let mut data = vec![0; s.len() * 2];
+                LittleEndian::write_i16_into(s, &mut data);
+                data
+            }
+            Samples::I24(s) => {
+                let mut data = vec![0; s.len() * 3];
+                for (bytes, &v) in data.chunks_exact_mut(3).zip(s.iter()) {
+                    LittleEndian::write_i24(bytes, v);
+                }
+                data
+            }
+            Samples::I32(s) => {
+                let mut data = vec![0; s.len() * 4];
+                LittleEndian::write_i32_into(s, &mut data);
+                data
+            }
+            Samples::F32(s) => {
+                let mut data = vec![0; s.len() * 4];
+                LittleEndian::write_f32_into(s, &mut data);
+                data
+            }
+            Samples::F64(s) => {
+                let mut data = vec![0; s.len() * 8];
+                LittleEndian::write_f64_into(s, &mut data);
+                data
+            }
+        }
+    }
+
+    /// The number of sample frames.
+    pub fn frames(&self) -> usize {
+        if self.channels == 0 {
+            return 0;
+        }
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Split the interleaved samples into one set of samples per channel.
+    pub fn deinterleave(&self) -> Vec<Samples> {
+        let channels = self.channels as usize;
+        match &self.samples {
+            Samples::U8(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::U8)
+                .collect(),
+            Samples::I16(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::I16)
+                .collect(),
+            Samples::I24(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::I24)
+                .collect(),
+            Samples::I32(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::I32)
+                .collect(),
+            Samples::F32(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::F32)
+                .collect(),
+            Samples::F64(s) => deinterleave(s, channels)
+                .into_iter()
+                .map(Samples::F64)
+                .collect(),
+        }
+    }
+
+    /// The samples of a single channel.
+    pub fn channel(&self, index: u16) -> Option<Samples> {
+        self.deinterleave().into_iter().nth(index as usize)
+    }
+
+    /// Build interleaved audio from one set of samples per channel. Every channel must use the
+    /// same sample format; the result is as long as the shortest channel.
+    pub fn interleave(channels: Vec<Samples>) -> Result<Self, WavTagError> {
+        let num_channels = channels.len() as u16;
+
+        macro_rules! interleave_as {
+            ($variant:ident) => {{
+                let channels = channels
+                    .iter()
+                    .map(|c| match c {
+                        Samples::$variant(s) => Ok(s),
+                        _ => Err(WavTagError::InvalidValue {
+                            field: "channels",
+                            reason: "channels have mixed sample formats".to_string(),
+                        }),
+                    })
+                    .collect::<Result<Vec<_>, WavTagError>>()?;
+                Samples::$variant(interleave(&channels))
+            }};
+        }
+
+        let samples = match channels.first() {
+            None => {
+                return Err(WavTagError::InvalidValue {
+                    field: "channels",
+                    reason: "no channels to interleave".to_string(),
+                })
+            }
+            Some(Samples::U8(_)) => interleave_as!(U8),
+            Some(Samples::I16(_)) => interleave_as!(I16),
+            Some(Samples::I24(_)) => interleave_as!(I24),
+            Some(Samples::I32(_)) => interleave_as!(I32),
+            Some(Samples::F32(_)) => interleave_as!(F32),
+            Some(Samples::F64(_)) => interleave_as!(F64),
+        };
+
+        Ok(AudioData {
+            channels: num_channels,
+            samples,
+        })
+    }
+}
+
+impl RiffFile {
+    /// Decode the data chunk using the format chunk. Compressed formats are expanded to 16-bit
+    /// PCM, with the padding of the last IMA ADPCM block removed.
+    pub fn samples(&self) -> Result<AudioData, WavTagError> {
+        let format = self.format()?;
+        let mut audio = match self.find_chunk_by_type(ChunkType::Data) {
+            Some(c) => AudioData::decode(&format, &self.read_chunk_data(c)?)?,
+            None => return Err(WavTagError::MissingChunk(ChunkType::Data)),
+        };
+
+        if let (FormatTag::ImaAdpcm, Samples::I16(samples)) =
+            (format.sample_format(), &mut audio.samples)
+        {
+            if let Some(frames) = self.fact_frames()? {
+                samples.truncate(frames * format.channels as usize);
+            }
+        }
+
+        Ok(audio)
+    }
+
+    /// The number of sample frames given by the fact chunk, which compressed formats use to
+    /// give the decoded length.
+    fn fact_frames(&self) -> Result<Option<usize>, WavTagError> {
+        let fact = match self.find_chunk_by_type(ChunkType::Fact) {
+            Some(c) => self.load_chunk(c)?,
+            None => return Ok(None),
+        };
+        Ok(fact
+            .data
+            .get(0..4)
+            .map(|frames| LittleEndian::read_u32(frames) as usize))
+    }
+
+    /// Replace the data chunk with encoded samples. The format chunk is updated to match the
+    /// samples, keeping the existing sample rate.
+    pub fn set_samples(&mut self, audio: &AudioData) -> Result<(), WavTagError> {
+        let current = self.format()?;
+        let (format_tag, bits_per_sample) = audio.samples.format();
+
+        if current.sample_format() != format_tag
+            || current.channels != audio.channels
+            || current.bits_per_sample != bits_per_sample
+            || current.block_align != audio.channels * bits_per_sample / 8
+        {
+            self.set_format(WaveFormat::new(
+                format_tag,
+                audio.channels,
+                current.sample_rate,
+                bits_per_sample,
+            ));
+        }
+
+        let data = audio.encode();
+        match self.chunks.iter_mut().find(|c| c.header == ChunkType::Data) {
+            Some(chunk) => {
+                chunk.data = data;
+                chunk.deferred = None;
+            }
+            None => self.chunks.push(RiffChunk::new(ChunkType::Data, data)),
+        }
+
+        Ok(())
+    }
+
+    /// Decode µ-law, A-law or IMA ADPCM audio to 16-bit PCM, for samplers that only read linear
+    /// PCM. Returns false if the audio wasn't compressed.
+    ///
+    /// Cue points in compressed data may address a block and an offset within it, so they're moved
+    /// to the sample frame they point at. Loop and cue points in the padding at the end of the
+    /// last IMA ADPCM block, which isn't decoded, are moved to the last frame.
+    pub fn transcode_to_pcm(&mut self) -> Result<bool, WavTagError> {
+        let format = self.format()?;
+        if !format.is_compressed() {
+            return Ok(false);
+        }
+
+        let audio = self.samples()?;
+        let frames = audio.frames() as u32;
+        let padded = self
+            .find_chunk_by_type(ChunkType::Data)
+            .map_or(0, |data| format.frame_count(data.len()) as u32);
+        let clamp = |position: u32| {
+            if position >= frames && position < padded {
+                frames.saturating_sub(1)
+            } else {
+                position
+            }
+        };
+
+        if self.find_chunk_by_type(ChunkType::Cue).is_some() {
+            let mut cue = self.get_cue_chunk()?;
+            let block_align = format.block_align.max(1) as u32;
+            let samples_per_block = format.samples_per_block() as u32;
+
+            for cue_point in cue.cue_points.iter_mut() {
+                let frame = if cue_point.block_start == 0 && cue_point.sample_offset == 0 {
+                    cue_point.position
+                } else {
+                    (cue_point.block_start / block_align)
+                        .saturating_mul(samples_per_block)
+                        .saturating_add(cue_point.sample_offset)
+                };
+
+                // PCM cue points are addressed by sample offset alone.
+                cue_point.position = clamp(frame);
+                cue_point.block_start = 0;
+                cue_point.sample_offset = cue_point.position;
+            }
+            self.set_cue_chunk(cue);
+        }
+
+        if padded > frames && self.find_chunk_by_type(ChunkType::Sampler).is_some() {
+            let mut smpl = self.get_sampler_chunk()?;
+            for sample_loop in smpl.sample_loops.iter_mut() {
+                sample_loop.start = clamp(sample_loop.start);
+                sample_loop.end = clamp(sample_loop.end);
+            }
+            self.set_sampler_chunk(smpl);
+        }
+
+        self.set_samples(&audio)?;
+
+        // the fact chunk is only needed for compressed formats.
+        self.chunks.retain(|c| c.header != ChunkType::Fact);
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RiffForm;
+
+    #[test]
+    fn round_trips_every_sample_format() {
+        let samples = [
+            Samples::U8(vec![0, 128, 255, 1]),
+            Samples::I16(vec![i16::MIN, -1, 0, i16::MAX]),
+            Samples::I24(vec![-8_388_608, -1, 0, 8_388_607]),
+            Samples::I32(vec![i32::MIN, -1, 0, i32::MAX]),
+            Samples::F32(vec![-1.0, -0.5, 0.0, 1.0]),
+            Samples::F64(vec![-1.0, -0.25, 0.0, 1.0]),
+        ];
+
+        for samples in samples {
+            let (format_tag, bits) = samples.format();
+            let format = WaveFormat::new(format_tag, 2, 44100, bits);
+            let audio = AudioData {
+                channels: 2,
+                samples,
+            };
+
+            let data = audio.encode();
+            assert_eq!(data.len(), 2 * format.block_align as usize);
+            // a trailing partial frame is ignored.
+            let mut padded = data.clone();
+            padded.push(0);
+            assert_eq!(AudioData::decode(&format, &padded).unwrap(), audio);
+            assert_eq!(audio.frames(), 2);
+        }
+
+        // 24-bit samples are packed into three bytes, little endian.
+        let audio = AudioData {
+            channels: 1,
+            samples: Samples::I24(vec![-2, 0x123456]),
+        };
+        assert_eq!(audio.encode(), [0xfe, 0xff, 0xff, 0x56, 0x34, 0x12]);
+    }
+
+    #[test]
+    fn splits_and_joins_channels() {
+        let audio = AudioData {
+            channels: 2,
+            samples: Samples::I16(vec![1, -1, 2, -2, 3, -3]),
+        };
+        let channels = audio.deinterleave();
+        assert_eq!(channels[1], Samples::I16(vec![-1, -2, -3]));
+        assert_eq!(audio.channel(0), Some(Samples::I16(vec![1, 2, 3])));
+        assert_eq!(AudioData::interleave(channels).unwrap(), audio);
+
+        let mixed = vec![Samples::I16(vec![1]), Samples::F32(vec![1.0])];
+        assert!(AudioData::interleave(mixed).is_err());
+    }
+
+    #[test]
+    fn updates_the_format_to_match_new_samples() {
+        let mut file = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(FormatTag::Pcm, 1, 22050, 16));
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, vec![0; 4]));
+
+        let audio = AudioData {
+            channels: 2,
+            samples: Samples::F32(vec![0.5, -0.5]),
+        };
+        file.set_samples(&audio).unwrap();
+
+        let format = file.format().unwrap();
+        assert_eq!(format, WaveFormat::new(FormatTag::IeeeFloat, 2, 22050, 32));
+        assert_eq!(file.chunks.len(), 2);
+        assert_eq!(file.samples().unwrap(), audio);
+    }
+}
@@ -0,0 +1,407 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::chunks::codecs::ima_adpcm_block_frames;
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+/// The trailing 14 bytes shared by every `KSDATAFORMAT_SUBTYPE_*` GUID. The first two bytes of
+/// the GUID hold the equivalent format tag (eg. 0x0001 for PCM).
+const SUBTYPE_GUID_SUFFIX: [u8; 14] = [
+    0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0x80, 0x00, 0x00, 0xAA, 0x00, 0x38, 0x9B, 0x71,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatTag {
+    /// 0x0001 - integer PCM
+    Pcm,
+    /// 0x0003 - IEEE floating point
+    IeeeFloat,
+    /// 0x0006 - ITU G.711 A-law
+    ALaw,
+    /// 0x0007 - ITU G.711 µ-law
+    MuLaw,
+    /// 0x0011 - IMA ADPCM
+    ImaAdpcm,
+    /// 0xFFFE - the real format is given by the sub format GUID of the extensible fields
+    Extensible,
+    Other(u16),
+}
+
+impl From<u16> for FormatTag {
+    fn from(tag: u16) -> Self {
+        match tag {
+            0x0001 => FormatTag::Pcm,
+            0x0003 => FormatTag::IeeeFloat,
+            0x0006 => FormatTag::ALaw,
+            0x0007 => FormatTag::MuLaw,
+            0x0011 => FormatTag::ImaAdpcm,
+            0xFFFE => FormatTag::Extensible,
+            other => FormatTag::Other(other),
+        }
+    }
+}
+
+impl From<FormatTag> for u16 {
+    fn from(tag: FormatTag) -> Self {
+        match tag {
+            FormatTag::Pcm => 0x0001,
+            FormatTag::IeeeFloat => 0x0003,
+            FormatTag::ALaw => 0x0006,
+            FormatTag::MuLaw => 0x0007,
+            FormatTag::ImaAdpcm => 0x0011,
+            FormatTag::Extensible => 0xFFFE,
+            FormatTag::Other(other) => other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaveFormat {
+    /// The format tag specifies how the data chunk is encoded.
+    pub format_tag: FormatTag,
+
+    /// The number of interleaved channels in the data chunk.
+    pub channels: u16,
+
+    /// The number of sample frames played per second (eg. 44100).
+    pub sample_rate: u32,
+
+    /// The average number of bytes per second, equal to `sample_rate * block_align` for PCM data.
+    pub byte_rate: u32,
+
+    /// The number of bytes in one sample frame (all channels), equal to
+    /// `channels * bits_per_sample / 8` for PCM data.
+    pub block_align: u16,
+
+    /// The container size of each sample in bits. Packed 24-bit audio uses 24.
+    pub bits_per_sample: u16,
+
+    /// The extra fields of WAVE_FORMAT_EXTENSIBLE, present when the format tag is `Extensible`.
+    pub extensible: Option<WaveFormatExtensible>,
+
+    /// Any other format specific bytes following the extension size field (eg. ADPCM block
+    /// information). `None` when the chunk has no extension size field at all, as in a plain
+    /// 16 byte PCM format chunk.
+    pub extra_data: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaveFormatExtensible {
+    /// The number of bits of precision in each sample, which may be less than the container
+    /// size given by `bits_per_sample` (eg. 20 valid bits in a 24 bit container).
+    pub valid_bits_per_sample: u16,
+
+    /// A bitmask of speaker positions assigned to the channels, in order (eg. 0x3 for front left
+    /// and front right). 0 means no particular assignment.
+    pub channel_mask: u32,
+
+    /// The GUID of the actual data format, eg. KSDATAFORMAT_SUBTYPE_PCM.
+    pub sub_format: [u8; 16],
+}
+
+impl WaveFormatExtensible {
+    /// The format tag that the sub format GUID corresponds to, if it is one of the standard
+    /// `KSDATAFORMAT_SUBTYPE_*` GUIDs.
+    pub fn sub_format_tag(&self) -> Option<FormatTag> {
+        if self.sub_format[2..] == SUBTYPE_GUID_SUFFIX {
+            Some(FormatTag::from(u16::from_le_bytes([
+                self.sub_format[0],
+                self.sub_format[1],
+            ])))
+        } else {
+            None
+        }
+    }
+
+    /// Build a standard sub format GUID for a format tag.
+    pub fn sub_format_for(tag: FormatTag) -> [u8; 16] {
+        let mut guid = [0u8; 16];
+        guid[0..2].copy_from_slice(&u16::from(tag).to_le_bytes());
+        guid[2..].copy_from_slice(&SUBTYPE_GUID_SUFFIX);
+        guid
+    }
+}
+
+impl WaveFormat {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Format {
+            return Err(WavTagError::wrong_chunk(ChunkType::Format, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+
+        let mut format = WaveFormat {
+            format_tag: FormatTag::from(data.read_u16::<LittleEndian>()?),
+            channels: data.read_u16::<LittleEndian>()?,
+            sample_rate: data.read_u32::<LittleEndian>()?,
+            byte_rate: data.read_u32::<LittleEndian>()?,
+            block_align: data.read_u16::<LittleEndian>()?,
+            bits_per_sample: data.read_u16::<LittleEndian>()?,
+            extensible: None,
+            extra_data: None,
+        };
+
+        // the extension size is only present in non-PCM (or WAVEFORMATEX) format chunks.
+        if let Ok(cb_size) = data.read_u16::<LittleEndian>() {
+            let mut extension = Vec::new();
+            data.take(cb_size as u64).read_to_end(&mut extension)?;
+
+            if format.format_tag == FormatTag::Extensible && extension.len() >= 22 {
+                let mut ext = Cursor::new(&extension);
+                format.extensible = Some(WaveFormatExtensible {
+                    valid_bits_per_sample: ext.read_u16::<LittleEndian>()?,
+                    channel_mask: ext.read_u32::<LittleEndian>()?,
+                    sub_format: {
+                        let mut guid = [0u8; 16];
+                        ext.read_exact(&mut guid)?;
+                        guid
+                    },
+                });
+                extension.drain(..22);
+            }
+
+            format.extra_data = Some(extension);
+        }
+
+        Ok(format)
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(40);
+
+        // writes to a vec are infallible.
+        chunk
+            .write_u16::<LittleEndian>(u16::from(self.format_tag))
+            .unwrap();
+        chunk.write_u16::<LittleEndian>(self.channels).unwrap();
+        chunk.write_u32::<LittleEndian>(self.sample_rate).unwrap();
+        chunk.write_u32::<LittleEndian>(self.byte_rate).unwrap();
+        chunk.write_u16::<LittleEndian>(self.block_align).unwrap();
+        chunk
+            .write_u16::<LittleEndian>(self.bits_per_sample)
+            .unwrap();
+
+        if self.extensible.is_some() || self.extra_data.is_some() {
+            let mut extension = Vec::new();
+
+            if let Some(ext) = &self.extensible {
+                extension
+                    .write_u16::<LittleEndian>(ext.valid_bits_per_sample)
+                    .unwrap();
+                extension
+                    .write_u32::<LittleEndian>(ext.channel_mask)
+                    .unwrap();
+                extension.extend_from_slice(&ext.sub_format);
+            }
+
+            if let Some(extra_data) = &self.extra_data {
+                extension.extend_from_slice(extra_data);
+            }
+
+            chunk
+                .write_u16::<LittleEndian>(extension.len() as u16)
+                .unwrap();
+            chunk.extend_from_slice(&extension);
+        }
+
+        chunk
+    }
+
+    /// Create a plain PCM or IEEE float format, using WAVE_FORMAT_EXTENSIBLE where the spec
+    /// requires it (more than two channels, or more than 16 bits per sample).
+    pub fn new(
+        format_tag: FormatTag,
+        channels: u16,
+        sample_rate: u32,
+        bits_per_sample: u16,
+    ) -> Self {
+        let block_align = channels * bits_per_sample.div_ceil(8);
+
+        let mut format = WaveFormat {
+            format_tag,
+            channels,
+            sample_rate,
+            byte_rate: sample_rate * block_align as u32,
+            block_align,
+            bits_per_sample,
+            extensible: None,
+            extra_data: None,
+        };
+
+        if channels > 2 || bits_per_sample > 16 {
+            format.format_tag = FormatTag::Extensible;
+            format.extensible = Some(WaveFormatExtensible {
+                valid_bits_per_sample: bits_per_sample,
+                channel_mask: 0,
+                sub_format: WaveFormatExtensible::sub_format_for(format_tag),
+            });
+            format.extra_data = Some(Vec::new());
+        } else if format_tag != FormatTag::Pcm {
+            format.extra_data = Some(Vec::new());
+        }
+
+        format
+    }
+
+    /// The actual encoding of the data chunk, looking through WAVE_FORMAT_EXTENSIBLE to the
+    /// sub format where present.
+    pub fn sample_format(&self) -> FormatTag {
+        match &self.extensible {
+            Some(ext) if self.format_tag == FormatTag::Extensible => {
+                ext.sub_format_tag().unwrap_or(FormatTag::Extensible)
+            }
+            _ => self.format_tag,
+        }
+    }
+
+    /// The number of bits of precision in each sample.
+    pub fn valid_bits_per_sample(&self) -> u16 {
+        match &self.extensible {
+            Some(ext) if ext.valid_bits_per_sample != 0 => ext.valid_bits_per_sample,
+            _ => self.bits_per_sample,
+        }
+    }
+
+    /// The duration of one sample frame in nanoseconds, as used by the sampler chunk.
+    pub fn sample_period(&self) -> u32 {
+        if self.sample_rate == 0 {
+            return 0;
+        }
+        (1_000_000_000_u64 / self.sample_rate as u64) as u32
+    }
+
+    /// Whether the data is in one of the compressed formats that `AudioData::decode` expands to
+    /// 16-bit PCM (µ-law, A-law or IMA ADPCM).
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self.sample_format(),
+            FormatTag::ALaw | FormatTag::MuLaw | FormatTag::ImaAdpcm
+        )
+    }
+
+    /// The number of sample frames in each block of IMA ADPCM data, from the extra format bytes
+    /// or calculated from the block align if they're missing. Other formats have one frame per
+    /// block.
+    pub fn samples_per_block(&self) -> usize {
+        if self.sample_format() != FormatTag::ImaAdpcm {
+            return 1;
+        }
+
+        match &self.extra_data {
+            Some(extra) if extra.len() >= 2 && extra[0..2] != [0, 0] => {
+                u16::from_le_bytes([extra[0], extra[1]]) as usize
+            }
+            _ => ima_adpcm_block_frames(
+                self.block_align as usize,
+                self.channels as usize,
+                usize::MAX,
+            ),
+        }
+    }
+
+    /// The number of sample frames in a data chunk of the given size. The last block of IMA
+    /// ADPCM data may be padded, in which case the fact chunk holds the real length.
+    pub fn frame_count(&self, data_len: usize) -> usize {
+        if self.block_align == 0 {
+            return 0;
+        }
+
+        let block_align = self.block_align as usize;
+        match self.sample_format() {
+            FormatTag::ImaAdpcm => {
+                let samples_per_block = self.samples_per_block();
+                data_len / block_align * samples_per_block
+                    + ima_adpcm_block_frames(
+                        data_len % block_align,
+                        self.channels as usize,
+                        samples_per_block,
+                    )
+            }
+            _ => data_len / block_align,
+        }
+    }
+}
+
+impl RiffFile {
+    pub fn format(&self) -> Result<WaveFormat, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Format) {
+            Some(c) => WaveFormat::from_chunk(&self.load_chunk(c)?),
+            None => Err(WavTagError::MissingChunk(ChunkType::Format)),
+        }
+    }
+
+    /// Replace the format chunk in place, or add it as the first chunk as it must come before
+    /// the data chunk.
+    pub fn set_format(&mut self, format: WaveFormat) {
+        let data = format.serialise();
+        match self
+            .chunks
+            .iter_mut()
+            .find(|c| c.header == ChunkType::Format)
+        {
+            Some(chunk) => {
+                chunk.data = data;
+                chunk.deferred = None;
+            }
+            None => self
+                .chunks
+                .insert(0, RiffChunk::new(ChunkType::Format, data)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_plain_and_extensible_formats() {
+        let formats = [
+            // a plain 16 byte PCM chunk, without the extension size.
+            (WaveFormat::new(FormatTag::Pcm, 2, 44100, 16), 16),
+            (WaveFormat::new(FormatTag::IeeeFloat, 1, 48000, 32), 40),
+            (WaveFormat::new(FormatTag::Pcm, 2, 96000, 24), 40),
+            (
+                WaveFormat {
+                    extensible: None,
+                    ..WaveFormat::new(FormatTag::ALaw, 1, 8000, 8)
+                },
+                18,
+            ),
+        ];
+
+        for (format, len) in formats {
+            let body = format.serialise();
+            assert_eq!(body.len(), len);
+
+            let chunk = RiffChunk::new(ChunkType::Format, body);
+            assert_eq!(WaveFormat::from_chunk(&chunk).unwrap(), format);
+        }
+    }
+
+    #[test]
+    fn reads_the_extensible_fields() {
+        let mut format = WaveFormat::new(FormatTag::Pcm, 2, 96000, 24);
+        format.extensible.as_mut().unwrap().valid_bits_per_sample = 20;
+        format.extensible.as_mut().unwrap().channel_mask = 0x3;
+
+        let body = format.serialise();
+        assert_eq!(body[..2], 0xfffeu16.to_le_bytes());
+        assert_eq!(body[16..18], 22u16.to_le_bytes());
+        assert_eq!(body[18..20], 20u16.to_le_bytes());
+        assert_eq!(body[20..24], 3u32.to_le_bytes());
+        assert_eq!(body[24..26], 1u16.to_le_bytes());
+
+        let read = WaveFormat::from_chunk(&RiffChunk::new(ChunkType::Format, body)).unwrap();
+        assert_eq!(read.format_tag, FormatTag::Extensible);
+        assert_eq!(read.sample_format(), FormatTag::Pcm);
+        assert_eq!(read.valid_bits_per_sample(), 20);
+        assert_eq!((read.block_align, read.byte_rate), (6, 576000));
+    }
+}
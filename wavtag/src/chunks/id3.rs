@@ -0,0 +1,651 @@
+use byteorder::{BigEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::{
+    ChunkType, RiffChunk, RiffFile, WavTagError, INFO_ARTIST, INFO_COMMENT, INFO_COPYRIGHT,
+    INFO_CREATION_DATE, INFO_GENRE, INFO_SOFTWARE, INFO_TITLE,
+};
+
+pub const ID3_TITLE: [u8; 4] = *b"TIT2";
+pub const ID3_ARTIST: [u8; 4] = *b"TPE1";
+pub const ID3_GENRE: [u8; 4] = *b"TCON";
+pub const ID3_COPYRIGHT: [u8; 4] = *b"TCOP";
+/// The software and settings used to encode the file.
+pub const ID3_SOFTWARE: [u8; 4] = *b"TSSE";
+/// The recording date of an ID3v2.4 tag, as YYYY-MM-DD or any shorter prefix.
+pub const ID3_RECORDING_TIME: [u8; 4] = *b"TDRC";
+/// The recording year of an ID3v2.3 tag, which has no full date frame.
+pub const ID3_YEAR: [u8; 4] = *b"TYER";
+/// The tempo in beats per minute.
+pub const ID3_BPM: [u8; 4] = *b"TBPM";
+/// The musical key, eg. "Am" or "F#".
+pub const ID3_KEY: [u8; 4] = *b"TKEY";
+pub const ID3_COMMENT: [u8; 4] = *b"COMM";
+
+/// The INFO fields that have an ID3 equivalent, which `RiffFile::sync_id3` mirrors. Keywords
+/// have no ID3 frame.
+pub const ID3_INFO_FIELDS: [[u8; 4]; 7] = [
+    INFO_TITLE,
+    INFO_ARTIST,
+    INFO_COMMENT,
+    INFO_GENRE,
+    INFO_COPYRIGHT,
+    INFO_CREATION_DATE,
+    INFO_SOFTWARE,
+];
+
+const FLAG_UNSYNCHRONISATION: u8 = 0x80;
+const FLAG_EXTENDED_HEADER: u8 = 0x40;
+
+// frame format flags, which moved between versions.
+const FRAME_V3_COMPRESSED: u16 = 0x0080;
+const FRAME_V3_ENCRYPTED: u16 = 0x0040;
+const FRAME_V3_GROUPED: u16 = 0x0020;
+const FRAME_V4_GROUPED: u16 = 0x0040;
+const FRAME_V4_COMPRESSED: u16 = 0x0008;
+const FRAME_V4_ENCRYPTED: u16 = 0x0004;
+const FRAME_V4_UNSYNCHRONISED: u16 = 0x0002;
+const FRAME_V4_DATA_LENGTH: u16 = 0x0001;
+
+const ENCODING_LATIN1: u8 = 0;
+const ENCODING_UTF16: u8 = 1;
+const ENCODING_UTF16BE: u8 = 2;
+const ENCODING_UTF8: u8 = 3;
+
+/// A frame of an ID3v2 tag, kept as it was read so frames that aren't understood (eg. pictures)
+/// are written back unchanged.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Id3Frame {
+    pub id: [u8; 4],
+    pub flags: u16,
+    pub data: Vec<u8>,
+}
+
+/// An ID3v2 tag embedded in an `id3 ` (or `ID3 `) chunk. Sample browsers such as Sononym and
+/// those in DAWs read the title, genre, tempo, key and comments from it rather than from the
+/// LIST/INFO chunk. ID3v2.3 and ID3v2.4 tags are supported; new tags are written as ID3v2.4.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Id3Chunk {
+    /// 3 or 4, for ID3v2.3 or ID3v2.4.
+    pub major_version: u8,
+    pub revision: u8,
+    pub frames: Vec<Id3Frame>,
+}
+
+impl Default for Id3Chunk {
+    fn default() -> Self {
+        Id3Chunk {
+            major_version: 4,
+            revision: 0,
+            frames: Vec::new(),
+        }
+    }
+}
+
+fn invalid(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason.to_string())
+}
+
+/// A 28-bit integer stored 7 bits to a byte so it can't be mistaken for a sync signal.
+fn read_syncsafe(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0u32, |acc, &b| acc << 7 | (b & 0x7f) as u32)
+}
+
+fn write_syncsafe(value: u32) -> [u8; 4] {
+    [
+        (value >> 21) as u8 & 0x7f,
+        (value >> 14) as u8 & 0x7f,
+        (value >> 7) as u8 & 0x7f,
+        value as u8 & 0x7f,
+    ]
+}
+
+/// Reverse unsynchronisation, which inserts a zero after every 0xFF byte.
+fn resynchronise(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    for (i, &b) in data.iter().enumerate() {
+        if b == 0 && i > 0 && data[i - 1] == 0xff {
+            continue;
+        }
+        out.push(b);
+    }
+    out
+}
+
+/// Decode text in one of the ID3 encodings. ID3v2.4 separates multiple values with a null, which
+/// are joined with "; " as INFO keywords are.
+fn decode_text(encoding: u8, data: &[u8]) -> String {
+    let text = match encoding {
+        ENCODING_LATIN1 => data.iter().map(|&b| b as char).collect(),
+        ENCODING_UTF16 | ENCODING_UTF16BE => {
+            let mut units: Vec<u16> = data.chunks_exact(2).map(BigEndian::read_u16).collect();
+            // without a byte order mark, big endian is assumed.
+            let little_endian = encoding == ENCODING_UTF16 && units.first() == Some(&0xfffe);
+            if little_endian {
+                units = units.iter().map(|u| u.swap_bytes()).collect();
+            }
+            let units: Vec<u16> = units.into_iter().filter(|&u| u != 0xfeff).collect();
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        }
+        _ => String::from_utf8_lossy(data).into_owned(),
+    };
+
+    text.split('\0')
+        .filter(|value| !value.is_empty())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Split a null terminated string from the front of `data`, returning the rest.
+fn split_terminated(encoding: u8, data: &[u8]) -> (&[u8], &[u8]) {
+    let end = match encoding {
+        ENCODING_UTF16 | ENCODING_UTF16BE => data
+            .chunks_exact(2)
+            .position(|unit| unit == [0, 0])
+            .map(|i| (i * 2, i * 2 + 2)),
+        _ => data.iter().position(|&b| b == 0).map(|i| (i, i + 1)),
+    };
+
+    match end {
+        Some((end, next)) => (&data[..end], &data[next..]),
+        None => (data, &[]),
+    }
+}
+
+impl Id3Chunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Id3 {
+            return Err(WavTagError::wrong_chunk(ChunkType::Id3, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+        let mut magic = [0u8; 3];
+        data.read_exact(&mut magic)?;
+        if &magic != b"ID3" {
+            return Err(invalid("expected an \"ID3\" header"));
+        }
+
+        let major_version = data.read_u8()?;
+        let revision = data.read_u8()?;
+        let flags = data.read_u8()?;
+        let mut size = [0u8; 4];
+        data.read_exact(&mut size)?;
+
+        if major_version != 3 && major_version != 4 {
+            return Err(invalid(&format!(
+                "ID3v2.{} tags aren't supported",
+                major_version
+            )));
+        }
+
+        let end = (10 + read_syncsafe(&size) as usize).min(body.len());
+        let mut tag = body[10..end].to_vec();
+
+        // ID3v2.4 unsynchronises each frame rather than the whole tag.
+        if major_version == 3 && flags & FLAG_UNSYNCHRONISATION != 0 {
+            tag = resynchronise(&tag);
+        }
+
+        let mut position = 0;
+        if flags & FLAG_EXTENDED_HEADER != 0 {
+            let header = tag
+                .get(0..4)
+                .ok_or_else(|| invalid("truncated extended header"))?;
+            position = match major_version {
+                3 => BigEndian::read_u32(header) as usize + 4,
+                _ => read_syncsafe(header) as usize,
+            };
+        }
+
+        let mut frames = Vec::new();
+        // frames are followed by padding, which starts with a zero byte.
+        while position + 10 <= tag.len() && tag[position] != 0 {
+            let header = &tag[position..position + 10];
+            let id = [header[0], header[1], header[2], header[3]];
+            let size = match major_version {
+                3 => BigEndian::read_u32(&header[4..8]),
+                _ => read_syncsafe(&header[4..8]),
+            } as usize;
+            let flags = BigEndian::read_u16(&header[8..10]);
+
+            let start = position + 10;
+            let data = tag
+                .get(start..start + size)
+                .ok_or_else(|| invalid(&format!("frame {:?} is truncated", id)))?;
+
+            frames.push(Id3Frame {
+                id,
+                flags,
+                data: data.to_vec(),
+            });
+            position = start + size;
+        }
+
+        Ok(Id3Chunk {
+            major_version,
+            revision,
+            frames,
+        })
+    }
+
+    /// The tag is written without unsynchronisation, an extended header or padding.
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut frames = Vec::new();
+
+        // writes to a vec are infallible.
+        for frame in self.frames.iter() {
+            frames.extend_from_slice(&frame.id);
+            match self.major_version {
+                3 => frames
+                    .write_u32::<BigEndian>(frame.data.len() as u32)
+                    .unwrap(),
+                _ => frames.extend_from_slice(&write_syncsafe(frame.data.len() as u32)),
+            }
+            frames.write_u16::<BigEndian>(frame.flags).unwrap();
+            frames.extend_from_slice(&frame.data);
+        }
+
+        let mut chunk = Vec::with_capacity(10 + frames.len());
+        chunk.extend_from_slice(b"ID3");
+        chunk.push(self.major_version);
+        chunk.push(self.revision);
+        chunk.push(0);
+        chunk.extend_from_slice(&write_syncsafe(frames.len() as u32));
+        chunk.extend_from_slice(&frames);
+
+        chunk
+    }
+
+    /// The body of a frame with any grouping or data length bytes removed and unsynchronisation
+    /// reversed. Compressed and encrypted frames can't be read.
+    fn frame_content(&self, frame: &Id3Frame) -> Option<Vec<u8>> {
+        let (compressed, encrypted, grouped) = match self.major_version {
+            3 => (FRAME_V3_COMPRESSED, FRAME_V3_ENCRYPTED, FRAME_V3_GROUPED),
+            _ => (FRAME_V4_COMPRESSED, FRAME_V4_ENCRYPTED, FRAME_V4_GROUPED),
+        };
+        if frame.flags & (compressed | encrypted) != 0 {
+            return None;
+        }
+
+        let mut data = frame.data.as_slice();
+        if frame.flags & grouped != 0 {
+            data = data.get(1..)?;
+        }
+        if self.major_version == 4 && frame.flags & FRAME_V4_DATA_LENGTH != 0 {
+            data = data.get(4..)?;
+        }
+        if self.major_version == 4 && frame.flags & FRAME_V4_UNSYNCHRONISED != 0 {
+            return Some(resynchronise(data));
+        }
+
+        Some(data.to_vec())
+    }
+
+    /// The text of a text information frame (`T...` other than `TXXX`) or a comment frame.
+    pub fn frame_text(&self, frame: &Id3Frame) -> Option<String> {
+        let content = self.frame_content(frame)?;
+        let (&encoding, text) = content.split_first()?;
+
+        match &frame.id {
+            b"COMM" => {
+                // a three letter language code and a description come before the text.
+                let (_, text) = split_terminated(encoding, text.get(3..)?);
+                Some(decode_text(encoding, text))
+            }
+            b"TXXX" => None,
+            [b'T', ..] => Some(decode_text(encoding, text)),
+            _ => None,
+        }
+    }
+
+    /// The encoding and bytes to write text in. ID3v2.3 has no UTF-8, so text that isn't Latin-1
+    /// is written as UTF-16.
+    fn encode_text(&self, text: &str) -> (u8, Vec<u8>) {
+        if self.major_version >= 4 {
+            (ENCODING_UTF8, text.as_bytes().to_vec())
+        } else if text.chars().all(|c| (c as u32) < 0x100) {
+            (ENCODING_LATIN1, text.chars().map(|c| c as u8).collect())
+        } else {
+            let mut data = vec![0xff, 0xfe];
+            for unit in text.encode_utf16() {
+                data.extend_from_slice(&unit.to_le_bytes());
+            }
+            (ENCODING_UTF16, data)
+        }
+    }
+
+    /// Replace the first frame with this id, removing any others, or add it to the end.
+    fn set_frame(&mut self, frame: Id3Frame) {
+        match self.frames.iter().position(|f| f.id == frame.id) {
+            Some(index) => {
+                let id = frame.id;
+                self.frames[index] = frame;
+
+                let mut i = 0;
+                self.frames.retain(|f| {
+                    let keep = i <= index || f.id != id;
+                    i += 1;
+                    keep
+                });
+            }
+            None => self.frames.push(frame),
+        }
+    }
+
+    pub fn get_text(&self, id: [u8; 4]) -> Option<String> {
+        self.frames
+            .iter()
+            .find(|f| f.id == id)
+            .and_then(|f| self.frame_text(f))
+    }
+
+    /// Set a text information frame. An empty value removes the frame.
+    pub fn set_text(&mut self, id: [u8; 4], value: &str) {
+        if value.is_empty() {
+            self.remove(id);
+            return;
+        }
+
+        let (encoding, text) = self.encode_text(value);
+        let mut data = vec![encoding];
+        data.extend_from_slice(&text);
+        self.set_frame(Id3Frame { id, flags: 0, data });
+    }
+
+    pub fn remove(&mut self, id: [u8; 4]) {
+        self.frames.retain(|f| f.id != id);
+    }
+
+    /// Whether a comment frame has a description. Comments with a description are usually
+    /// written by other software for its own use (eg. iTunes normalisation).
+    fn is_described(&self, frame: &Id3Frame) -> bool {
+        self.frame_content(frame)
+            .is_some_and(|content| match content.split_first() {
+                Some((&encoding, rest)) if rest.len() > 3 => {
+                    !decode_text(encoding, split_terminated(encoding, &rest[3..]).0).is_empty()
+                }
+                _ => false,
+            })
+    }
+
+    /// The first comment without a description, or failing that the first comment.
+    pub fn comment(&self) -> Option<String> {
+        let mut comments = self.frames.iter().filter(|f| f.id == ID3_COMMENT);
+        let frame = match comments.clone().find(|f| !self.is_described(f)) {
+            Some(frame) => frame,
+            None => comments.next()?,
+        };
+        self.frame_text(frame)
+    }
+
+    /// Set the comment, replacing any comments without a description. An empty value removes
+    /// them.
+    pub fn set_comment(&mut self, comment: &str) {
+        let position = self
+            .frames
+            .iter()
+            .position(|f| f.id == ID3_COMMENT && !self.is_described(f));
+        let described: Vec<bool> = self.frames.iter().map(|f| self.is_described(f)).collect();
+        let mut i = 0;
+        self.frames.retain(|f| {
+            let keep = f.id != ID3_COMMENT || described[i];
+            i += 1;
+            keep
+        });
+
+        if comment.is_empty() {
+            return;
+        }
+
+        let (encoding, text) = self.encode_text(comment);
+        let mut data = vec![encoding];
+        data.extend_from_slice(b"eng");
+        // an empty description.
+        match encoding {
+            ENCODING_UTF16 => data.extend_from_slice(&[0xff, 0xfe, 0, 0]),
+            _ => data.push(0),
+        }
+        data.extend_from_slice(&text);
+
+        let frame = Id3Frame {
+            id: ID3_COMMENT,
+            flags: 0,
+            data,
+        };
+        match position {
+            Some(index) => self.frames.insert(index, frame),
+            None => self.frames.push(frame),
+        }
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.get_text(ID3_TITLE)
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.set_text(ID3_TITLE, title)
+    }
+
+    pub fn artist(&self) -> Option<String> {
+        self.get_text(ID3_ARTIST)
+    }
+
+    pub fn set_artist(&mut self, artist: &str) {
+        self.set_text(ID3_ARTIST, artist)
+    }
+
+    pub fn genre(&self) -> Option<String> {
+        self.get_text(ID3_GENRE)
+    }
+
+    pub fn set_genre(&mut self, genre: &str) {
+        self.set_text(ID3_GENRE, genre)
+    }
+
+    pub fn bpm(&self) -> Option<String> {
+        self.get_text(ID3_BPM)
+    }
+
+    pub fn set_bpm(&mut self, bpm: &str) {
+        self.set_text(ID3_BPM, bpm)
+    }
+
+    pub fn key(&self) -> Option<String> {
+        self.get_text(ID3_KEY)
+    }
+
+    pub fn set_key(&mut self, key: &str) {
+        self.set_text(ID3_KEY, key)
+    }
+
+    /// The frame holding the same field as an INFO key, for the fields in `ID3_INFO_FIELDS`
+    /// other than the comment.
+    fn info_frame(&self, key: [u8; 4]) -> Option<[u8; 4]> {
+        match key {
+            INFO_TITLE => Some(ID3_TITLE),
+            INFO_ARTIST => Some(ID3_ARTIST),
+            INFO_GENRE => Some(ID3_GENRE),
+            INFO_COPYRIGHT => Some(ID3_COPYRIGHT),
+            INFO_SOFTWARE => Some(ID3_SOFTWARE),
+            INFO_CREATION_DATE if self.major_version == 3 => Some(ID3_YEAR),
+            INFO_CREATION_DATE => Some(ID3_RECORDING_TIME),
+            _ => None,
+        }
+    }
+
+    /// The value of the field an INFO key (eg. `INFO_TITLE`) maps to.
+    pub fn info_value(&self, key: [u8; 4]) -> Option<String> {
+        match key {
+            INFO_COMMENT => self.comment(),
+            key => self.info_frame(key).and_then(|id| self.get_text(id)),
+        }
+    }
+
+    /// Set the field an INFO key maps to. Keys without an ID3 equivalent are ignored.
+    pub fn set_info_value(&mut self, key: [u8; 4], value: &str) {
+        match (key, self.info_frame(key)) {
+            (INFO_COMMENT, _) => self.set_comment(value),
+            // ID3v2.3 only has the year.
+            (INFO_CREATION_DATE, Some(ID3_YEAR)) => {
+                self.set_text(ID3_YEAR, &value.chars().take(4).collect::<String>())
+            }
+            (_, Some(id)) => self.set_text(id, value),
+            (_, None) => {}
+        }
+    }
+}
+
+impl RiffFile {
+    /// Large tags (eg. with artwork) may have been left in the source file by `open_lazy`, so the
+    /// chunk is read through `read_chunk_data`.
+    pub fn get_id3_chunk(&self) -> Result<Id3Chunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Id3) {
+            Some(c) => Id3Chunk::read_body(&self.read_chunk_data(c)?)
+                .map_err(|e| WavTagError::from_body(c, e)),
+            None => Ok(Id3Chunk::default()),
+        }
+    }
+
+    /// Replace the id3 chunk, keeping the `ID3 ` spelling of the tag if the file uses it.
+    pub fn set_id3_chunk(&mut self, chunk: Id3Chunk) {
+        let mut chunk = RiffChunk::new(ChunkType::Id3, chunk.serialise());
+        if let Some(existing) = self.find_chunk_by_type(ChunkType::Id3) {
+            chunk.tag = existing.tag;
+        }
+        self.add_or_replace_chunk_by_type(chunk);
+    }
+
+    /// Mirror the fields in `ID3_INFO_FIELDS` between the LIST/INFO and id3 chunks, so every
+    /// browser shows the same values. Where both have a value the INFO value is kept. Neither
+    /// chunk is added if there is nothing to put in it.
+    pub fn sync_id3(&mut self) -> Result<(), WavTagError> {
+        let mut info = self.get_info_chunk()?;
+        let mut id3 = self.get_id3_chunk()?;
+
+        for key in ID3_INFO_FIELDS {
+            match info.get(key) {
+                Some(value) => id3.set_info_value(key, value),
+                None => {
+                    if let Some(value) = id3.info_value(key) {
+                        info.set(key, &value);
+                    }
+                }
+            }
+        }
+
+        if !info.entries.is_empty() {
+            self.set_info_chunk(info);
+        }
+        if !id3.frames.is_empty() {
+            self.set_id3_chunk(id3);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InfoChunk, RiffForm, INFO_GENRE};
+
+    fn empty_wav() -> RiffFile {
+        RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn writes_plain_sizes_in_v3_and_syncsafe_sizes_in_v4() {
+        for (major_version, size) in [(3, [0, 0, 0, 0xc8]), (4, [0, 0, 1, 0x48])] {
+            let mut id3 = Id3Chunk {
+                major_version,
+                ..Id3Chunk::default()
+            };
+            id3.set_title(&"a".repeat(199));
+
+            let body = id3.serialise();
+            assert_eq!(&body[..4], [b'I', b'D', b'3', major_version]);
+            // the tag size is syncsafe in both versions.
+            assert_eq!(body[6..10], write_syncsafe(210));
+            assert_eq!(&body[10..14], b"TIT2");
+            assert_eq!(body[14..18], size);
+
+            let read = Id3Chunk::read_body(&body).unwrap();
+            assert_eq!(read, id3);
+            assert_eq!(read.title(), Some("a".repeat(199)));
+        }
+    }
+
+    #[test]
+    fn keeps_the_case_of_the_chunk_tag() {
+        let mut id3 = Id3Chunk::default();
+        id3.set_title("Kick");
+
+        for tag in [*b"id3 ", *b"ID3 "] {
+            let mut chunk = RiffChunk::new(ChunkType::Id3, id3.serialise());
+            chunk.tag = tag;
+            let mut file = empty_wav();
+            file.chunks.push(chunk);
+            assert_eq!(
+                file.get_id3_chunk().unwrap().title(),
+                Some("Kick".to_string())
+            );
+
+            let mut replaced = Id3Chunk::default();
+            replaced.set_title("Snare");
+            file.set_id3_chunk(replaced);
+            assert_eq!(file.chunks.len(), 1);
+            assert_eq!(file.chunks[0].tag, tag);
+            assert_eq!(
+                file.get_id3_chunk().unwrap().title(),
+                Some("Snare".to_string())
+            );
+        }
+
+        let mut file = empty_wav();
+        file.set_id3_chunk(id3);
+        assert_eq!(file.chunks[0].tag, *b"id3 ");
+    }
+
+    #[test]
+    fn syncs_fields_both_ways() {
+        let mut file = empty_wav();
+        let mut info = InfoChunk::default();
+        info.set(INFO_TITLE, "From INFO");
+        info.set(INFO_ARTIST, "INFO artist");
+        file.set_info_chunk(info);
+
+        let mut id3 = Id3Chunk {
+            major_version: 3,
+            ..Id3Chunk::default()
+        };
+        id3.set_genre("From ID3");
+        id3.set_artist("ID3 artist");
+        file.set_id3_chunk(id3);
+
+        file.sync_id3().unwrap();
+
+        let info = file.get_info_chunk().unwrap();
+        let id3 = file.get_id3_chunk().unwrap();
+        assert_eq!(id3.title(), Some("From INFO".to_string()));
+        assert_eq!(info.get(INFO_GENRE), Some("From ID3"));
+        // the INFO value wins where both have one.
+        assert_eq!(info.get(INFO_ARTIST), Some("INFO artist"));
+        assert_eq!(id3.artist(), Some("INFO artist".to_string()));
+        assert_eq!(id3.major_version, 3);
+    }
+}
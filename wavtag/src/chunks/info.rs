@@ -0,0 +1,183 @@
+use crate::chunks::list::{read_list, read_zstr, write_list, write_zstr, SubChunk};
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+const INFO: [u8; 4] = *b"INFO";
+
+/// The title of the material, eg. the instrument name.
+pub const INFO_TITLE: [u8; 4] = *b"INAM";
+/// The artist or author of the material.
+pub const INFO_ARTIST: [u8; 4] = *b"IART";
+/// General comments about the file.
+pub const INFO_COMMENT: [u8; 4] = *b"ICMT";
+/// Keywords, separated by a semicolon and a space (eg. "Pad; Analog").
+pub const INFO_KEYWORDS: [u8; 4] = *b"IKEY";
+/// The genre of the material.
+pub const INFO_GENRE: [u8; 4] = *b"IGNR";
+/// Copyright information for the material.
+pub const INFO_COPYRIGHT: [u8; 4] = *b"ICOP";
+/// The date the material was created, as YYYY-MM-DD.
+pub const INFO_CREATION_DATE: [u8; 4] = *b"ICRD";
+/// The software package used to create the file.
+pub const INFO_SOFTWARE: [u8; 4] = *b"ISFT";
+
+/// A LIST chunk of form type `INFO`, holding text metadata keyed by four character codes
+/// such as `INAM` (title) and `IART` (artist).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InfoChunk {
+    pub entries: Vec<([u8; 4], String)>,
+}
+
+impl InfoChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        let (list_type, sub_chunks) = read_list(chunk)?;
+
+        if list_type != INFO {
+            return Err(WavTagError::malformed(chunk, "not an INFO list"));
+        };
+
+        Ok(InfoChunk {
+            entries: sub_chunks
+                .into_iter()
+                .map(|(key, value)| (key, read_zstr(&value)))
+                .collect(),
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let sub_chunks: Vec<SubChunk> = self
+            .entries
+            .iter()
+            .map(|(key, value)| {
+                let mut data = Vec::new();
+                write_zstr(&mut data, value);
+                (*key, data)
+            })
+            .collect();
+        write_list(INFO, &sub_chunks).data
+    }
+
+    pub fn get(&self, key: [u8; 4]) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Set the value for a key, keeping its position if it is already present. An empty value
+    /// removes the key.
+    pub fn set(&mut self, key: [u8; 4], value: &str) {
+        if value.is_empty() {
+            self.remove(key);
+        } else if let Some(entry) = self.entries.iter_mut().find(|(k, _)| *k == key) {
+            entry.1 = value.to_string();
+        } else {
+            self.entries.push((key, value.to_string()));
+        }
+    }
+
+    pub fn remove(&mut self, key: [u8; 4]) {
+        self.entries.retain(|(k, _)| *k != key);
+    }
+
+    pub fn title(&self) -> Option<&str> {
+        self.get(INFO_TITLE)
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.set(INFO_TITLE, title)
+    }
+
+    pub fn artist(&self) -> Option<&str> {
+        self.get(INFO_ARTIST)
+    }
+
+    pub fn set_artist(&mut self, artist: &str) {
+        self.set(INFO_ARTIST, artist)
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.get(INFO_COMMENT)
+    }
+
+    pub fn set_comment(&mut self, comment: &str) {
+        self.set(INFO_COMMENT, comment)
+    }
+
+    pub fn keywords(&self) -> Option<&str> {
+        self.get(INFO_KEYWORDS)
+    }
+
+    pub fn set_keywords(&mut self, keywords: &str) {
+        self.set(INFO_KEYWORDS, keywords)
+    }
+
+    pub fn genre(&self) -> Option<&str> {
+        self.get(INFO_GENRE)
+    }
+
+    pub fn set_genre(&mut self, genre: &str) {
+        self.set(INFO_GENRE, genre)
+    }
+
+    pub fn copyright(&self) -> Option<&str> {
+        self.get(INFO_COPYRIGHT)
+    }
+
+    pub fn set_copyright(&mut self, copyright: &str) {
+        self.set(INFO_COPYRIGHT, copyright)
+    }
+}
+
+impl RiffFile {
+    pub fn get_info_chunk(&self) -> Result<InfoChunk, WavTagError> {
+        match self.find_list_chunk_by_type(INFO) {
+            Some(c) => InfoChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(InfoChunk::default()),
+        }
+    }
+
+    pub fn set_info_chunk(&mut self, chunk: InfoChunk) {
+        self.add_or_replace_list_chunk_by_type(
+            INFO,
+            RiffChunk::new(ChunkType::List, chunk.serialise()),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_info_entries() {
+        let mut info = InfoChunk::default();
+        info.set_title("Pad");
+        info.set_artist("Someone");
+        info.set_keywords("Pad; Analog");
+        info.set_comment("Émoji 🎹");
+
+        let body = info.serialise();
+        assert_eq!(&body[..8], b"INFOINAM");
+        // "Pad\0" is even, so the artist follows without a pad byte.
+        assert_eq!(body[8..12], 4u32.to_le_bytes());
+        assert_eq!(&body[12..20], b"Pad\0IART");
+
+        let chunk = RiffChunk::new(ChunkType::List, body);
+        assert_eq!(InfoChunk::from_chunk(&chunk).unwrap(), info);
+
+        // setting a key again keeps its place, and an empty value removes it.
+        info.set_title("Lead");
+        info.set_artist("");
+        assert_eq!(
+            info.entries.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            [INFO_TITLE, INFO_KEYWORDS, INFO_COMMENT]
+        );
+        assert_eq!(info.title(), Some("Lead"));
+    }
+
+    #[test]
+    fn refuses_other_lists() {
+        let chunk = RiffChunk::new(ChunkType::List, b"adtl".to_vec());
+        assert!(InfoChunk::from_chunk(&chunk).is_err());
+    }
+}
@@ -1,9 +1,9 @@
 use std::io;
-use std::io::{Cursor, Error, ErrorKind};
+use std::io::Cursor;
 
 use byteorder::ReadBytesExt;
 
-use crate::{ChunkType, RiffChunk, RiffFile};
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct InstrumentChunk {
@@ -16,12 +16,12 @@ pub struct InstrumentChunk {
     /// The fine tune value specifies how much the sample's pitch should be altered when the sound is played back
     /// in cents (1/100 of a semitone). A negative value means that the pitch should be played lower and a positive
     /// value means that it should be played at a higher pitch.
-    pub fine_tune: u8, // -50 - +50
+    pub fine_tune: i8, // -50 - +50
 
     /// The gain value specifies the number of decibels to adjust the output when it is played. A value of 0dB
     /// means no change, 6dB means double the amplitude of each sample and -6dB means to halve the amplitude of
     /// each sample. Every additional +/-6dB will double or halve the amplitude again.
-    pub gain: u8, // -64 - +64
+    pub gain: i8, // -64 - +64
     pub low_note: u8,
     pub high_note: u8,
     pub low_vel: u8,
@@ -43,20 +43,21 @@ impl Default for InstrumentChunk {
 }
 
 impl InstrumentChunk {
-    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, io::Error> {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
         if chunk.header != ChunkType::Instrument {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "attempted from_chunk() on non-instrument chunk",
-            ));
+            return Err(WavTagError::wrong_chunk(ChunkType::Instrument, chunk));
         };
 
-        let mut data = Cursor::new(&chunk.data);
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
 
         Ok(InstrumentChunk {
             unshifted_note: data.read_u8()?,
-            fine_tune: data.read_u8()?,
-            gain: data.read_u8()?,
+            fine_tune: data.read_i8()?,
+            gain: data.read_i8()?,
             low_note: data.read_u8()?,
             high_note: data.read_u8()?,
             low_vel: data.read_u8()?,
@@ -64,11 +65,46 @@ impl InstrumentChunk {
         })
     }
 
+    /// Check the fields are within the ranges allowed by the spec.
+    pub fn validate(&self) -> Result<(), WavTagError> {
+        if !(-50..=50).contains(&self.fine_tune) {
+            return Err(WavTagError::InvalidValue {
+                field: "fine tune",
+                reason: format!("{} cents is outside -50 to +50", self.fine_tune),
+            });
+        }
+
+        if !(-64..=64).contains(&self.gain) {
+            return Err(WavTagError::InvalidValue {
+                field: "gain",
+                reason: format!("{}dB is outside -64 to +64", self.gain),
+            });
+        }
+
+        if [
+            self.unshifted_note,
+            self.low_note,
+            self.high_note,
+            self.low_vel,
+            self.high_vel,
+        ]
+        .iter()
+        .any(|&v| v > 127)
+        {
+            return Err(WavTagError::InvalidValue {
+                field: "note or velocity",
+                reason: "values must be 0-127".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn serialise(&self) -> Vec<u8> {
         vec![
             self.unshifted_note,
-            self.fine_tune,
-            self.gain,
+            self.fine_tune as u8,
+            self.gain as u8,
             self.low_note,
             self.high_note,
             self.low_vel,
@@ -79,20 +115,58 @@ impl InstrumentChunk {
 }
 
 impl RiffFile {
-    pub fn get_instrument_chunk(&self) -> InstrumentChunk {
+    pub fn get_instrument_chunk(&self) -> Result<InstrumentChunk, WavTagError> {
         match self.find_chunk_by_type(ChunkType::Instrument) {
-            Some(c) => {
-                InstrumentChunk::from_chunk(c).expect("chunk to be a valid instrument chunk")
-            }
-            None => InstrumentChunk::default(),
+            Some(c) => InstrumentChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(InstrumentChunk::default()),
         }
     }
 
     pub fn set_instrument_chunk(&mut self, chunk: InstrumentChunk) {
-        self.add_or_replace_chunk_by_type(RiffChunk {
-            header: ChunkType::Instrument,
-            data: chunk.serialise(),
-        });
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Instrument, chunk.serialise()));
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_signed_fine_tune_and_gain() {
+        let inst = InstrumentChunk {
+            unshifted_note: 48,
+            fine_tune: -50,
+            gain: -6,
+            low_note: 36,
+            high_note: 60,
+            low_vel: 1,
+            high_vel: 100,
+        };
+
+        let body = inst.serialise();
+        assert_eq!(body, [48, 0xce, 0xfa, 36, 60, 1, 100]);
+
+        let chunk = RiffChunk::new(ChunkType::Instrument, body);
+        let read = InstrumentChunk::from_chunk(&chunk).unwrap();
+        assert_eq!(read, inst);
+        assert!(read.validate().is_ok());
+    }
+
+    #[test]
+    fn refuses_values_out_of_range() {
+        let valid = InstrumentChunk::default();
+        for inst in [
+            InstrumentChunk {
+                fine_tune: 51,
+                ..valid
+            },
+            InstrumentChunk { gain: -65, ..valid },
+            InstrumentChunk {
+                high_vel: 128,
+                ..valid
+            },
+        ] {
+            assert!(inst.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::io;
+use std::io::Error;
+
+use xmltree::{Element, EmitterConfig, XMLNode};
+
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
+
+/// The iXML chunk holds production metadata written by field recorders (project, scene, take,
+/// track names and so on) as an XML document with a `BWFXML` root element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IxmlChunk {
+    pub root: Element,
+}
+
+/// A `TRACK` entry of the iXML `TRACK_LIST`, naming one channel of the recording.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IxmlTrack {
+    /// The channel number on the recorder (1 based).
+    pub channel_index: u16,
+
+    /// The position of the track within the interleaved data chunk (1 based).
+    pub interleave_index: u16,
+
+    pub name: String,
+    pub function: String,
+}
+
+impl Default for IxmlChunk {
+    fn default() -> Self {
+        let mut root = Element::new("BWFXML");
+        let mut version = Element::new("IXML_VERSION");
+        version.children.push(XMLNode::Text("1.5".to_string()));
+        root.children.push(XMLNode::Element(version));
+        IxmlChunk { root }
+    }
+}
+
+fn child_text(element: &Element, name: &str) -> Option<String> {
+    element
+        .get_child(name)
+        .and_then(|c| c.get_text())
+        .map(|t| t.into_owned())
+}
+
+/// Set the text of a child element, creating the child if it doesn't exist.
+fn set_child_text(element: &mut Element, name: &str, value: &str) {
+    if element.get_child(name).is_none() {
+        element.children.push(XMLNode::Element(Element::new(name)));
+    }
+
+    let child = element.get_mut_child(name).expect("child to exist");
+    child.children = vec![XMLNode::Text(value.to_string())];
+}
+
+impl IxmlChunk {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
+        if chunk.header != ChunkType::Ixml {
+            return Err(WavTagError::wrong_chunk(ChunkType::Ixml, chunk));
+        };
+
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        // recorders often pad the chunk out with nulls so it can be rewritten in place.
+        let end = body.iter().rposition(|&b| b != 0).map_or(0, |p| p + 1);
+
+        let root = Element::parse(&body[..end])
+            .map_err(|e| Error::other(format!("not valid XML: {}", e)))?;
+
+        Ok(IxmlChunk { root })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::new();
+
+        self.root
+            .write_with_config(&mut chunk, EmitterConfig::new().perform_indent(true))
+            .expect("iXML to serialise to a vec");
+
+        chunk
+    }
+
+    /// The text of a top level element, eg. `PROJECT`.
+    pub fn get(&self, name: &str) -> Option<String> {
+        child_text(&self.root, name)
+    }
+
+    /// Set the text of a top level element, adding it if it is missing.
+    pub fn set(&mut self, name: &str, value: &str) {
+        set_child_text(&mut self.root, name, value);
+    }
+
+    pub fn project(&self) -> Option<String> {
+        self.get("PROJECT")
+    }
+
+    pub fn set_project(&mut self, project: &str) {
+        self.set("PROJECT", project)
+    }
+
+    pub fn scene(&self) -> Option<String> {
+        self.get("SCENE")
+    }
+
+    pub fn set_scene(&mut self, scene: &str) {
+        self.set("SCENE", scene)
+    }
+
+    pub fn take(&self) -> Option<String> {
+        self.get("TAKE")
+    }
+
+    pub fn set_take(&mut self, take: &str) {
+        self.set("TAKE", take)
+    }
+
+    pub fn tape(&self) -> Option<String> {
+        self.get("TAPE")
+    }
+
+    pub fn set_tape(&mut self, tape: &str) {
+        self.set("TAPE", tape)
+    }
+
+    pub fn note(&self) -> Option<String> {
+        self.get("NOTE")
+    }
+
+    pub fn set_note(&mut self, note: &str) {
+        self.set("NOTE", note)
+    }
+
+    /// The tracks listed in `TRACK_LIST`.
+    pub fn tracks(&self) -> Vec<IxmlTrack> {
+        let track_list = match self.root.get_child("TRACK_LIST") {
+            Some(t) => t,
+            None => return Vec::new(),
+        };
+
+        track_list
+            .children
+            .iter()
+            .filter_map(|n| n.as_element())
+            .filter(|e| e.name == "TRACK")
+            .map(|track| IxmlTrack {
+                channel_index: child_text(track, "CHANNEL_INDEX")
+                    .and_then(|i| i.trim().parse().ok())
+                    .unwrap_or(0),
+                interleave_index: child_text(track, "INTERLEAVE_INDEX")
+                    .and_then(|i| i.trim().parse().ok())
+                    .unwrap_or(0),
+                name: child_text(track, "NAME").unwrap_or_default(),
+                function: child_text(track, "FUNCTION").unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Name the track with the given channel index, adding it to `TRACK_LIST` if needed.
+    pub fn set_track_name(&mut self, channel_index: u16, name: &str) {
+        if self.root.get_child("TRACK_LIST").is_none() {
+            self.root
+                .children
+                .push(XMLNode::Element(Element::new("TRACK_LIST")));
+        }
+
+        let track_list = self
+            .root
+            .get_mut_child("TRACK_LIST")
+            .expect("track list to exist");
+
+        let position = track_list.children.iter().position(|n| {
+            n.as_element().is_some_and(|e| {
+                e.name == "TRACK"
+                    && child_text(e, "CHANNEL_INDEX").map(|i| i.trim().to_string())
+                        == Some(channel_index.to_string())
+            })
+        });
+
+        match position {
+            Some(p) => {
+                let track = track_list.children[p]
+                    .as_mut_element()
+                    .expect("track to be an element");
+                set_child_text(track, "NAME", name);
+            }
+            None => {
+                let mut track = Element::new("TRACK");
+                set_child_text(&mut track, "CHANNEL_INDEX", &channel_index.to_string());
+                set_child_text(&mut track, "INTERLEAVE_INDEX", &channel_index.to_string());
+                set_child_text(&mut track, "NAME", name);
+                track_list.children.push(XMLNode::Element(track));
+            }
+        }
+
+        let track_count = track_list
+            .children
+            .iter()
+            .filter(|n| n.as_element().is_some_and(|e| e.name == "TRACK"))
+            .count();
+        if track_list.get_child("TRACK_COUNT").is_none() {
+            track_list
+                .children
+                .insert(0, XMLNode::Element(Element::new("TRACK_COUNT")));
+        }
+        set_child_text(track_list, "TRACK_COUNT", &track_count.to_string());
+    }
+}
+
+impl RiffFile {
+    pub fn get_ixml_chunk(&self) -> Result<IxmlChunk, WavTagError> {
+        match self.find_chunk_by_type(ChunkType::Ixml) {
+            Some(c) => IxmlChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(IxmlChunk::default()),
+        }
+    }
+
+    pub fn set_ixml_chunk(&mut self, chunk: IxmlChunk) {
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Ixml, chunk.serialise()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDER_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\
+        <BWFXML><IXML_VERSION>1.5</IXML_VERSION><PROJECT>Film</PROJECT><SCENE>12A</SCENE>\
+        <TAKE>3</TAKE><TRACK_LIST><TRACK_COUNT>2</TRACK_COUNT>\
+        <TRACK><CHANNEL_INDEX>1</CHANNEL_INDEX><INTERLEAVE_INDEX>1</INTERLEAVE_INDEX>\
+        <NAME>Boom</NAME><FUNCTION>MS-M</FUNCTION></TRACK>\
+        <TRACK><CHANNEL_INDEX>2</CHANNEL_INDEX><INTERLEAVE_INDEX>2</INTERLEAVE_INDEX>\
+        <NAME>Lav</NAME></TRACK></TRACK_LIST></BWFXML>";
+
+    #[test]
+    fn reads_recorder_fields_and_tracks() {
+        // padded with nulls for rewriting in place.
+        let mut body = RECORDER_XML.as_bytes().to_vec();
+        body.resize(body.len() + 64, 0);
+        let ixml = IxmlChunk::from_chunk(&RiffChunk::new(ChunkType::Ixml, body)).unwrap();
+
+        assert_eq!(ixml.project(), Some("Film".to_string()));
+        assert_eq!(ixml.scene(), Some("12A".to_string()));
+        assert_eq!(ixml.take(), Some("3".to_string()));
+        assert_eq!(ixml.note(), None);
+        assert_eq!(
+            ixml.tracks(),
+            [
+                IxmlTrack {
+                    channel_index: 1,
+                    interleave_index: 1,
+                    name: "Boom".to_string(),
+                    function: "MS-M".to_string(),
+                },
+                IxmlTrack {
+                    channel_index: 2,
+                    interleave_index: 2,
+                    name: "Lav".to_string(),
+                    function: String::new(),
+                },
+            ]
+        );
+
+        let chunk = RiffChunk::new(ChunkType::Ixml, ixml.serialise());
+        assert_eq!(IxmlChunk::from_chunk(&chunk).unwrap(), ixml);
+    }
+
+    #[test]
+    fn round_trips_new_fields_and_tracks() {
+        let mut ixml = IxmlChunk::default();
+        ixml.set_project("Doc");
+        ixml.set_note("wind");
+        ixml.set_track_name(2, "Left");
+        ixml.set_track_name(2, "Right");
+        ixml.set_track_name(1, "Mid");
+
+        let chunk = RiffChunk::new(ChunkType::Ixml, ixml.serialise());
+        let read = IxmlChunk::from_chunk(&chunk).unwrap();
+        assert_eq!(read, ixml);
+        assert_eq!(read.get("IXML_VERSION"), Some("1.5".to_string()));
+        assert_eq!(read.note(), Some("wind".to_string()));
+
+        let names: Vec<(u16, String)> = read
+            .tracks()
+            .into_iter()
+            .map(|t| (t.channel_index, t.name))
+            .collect();
+        assert_eq!(names, [(2, "Right".to_string()), (1, "Mid".to_string())]);
+        let track_list = read.root.get_child("TRACK_LIST").unwrap();
+        assert_eq!(child_text(track_list, "TRACK_COUNT"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn refuses_broken_xml() {
+        let chunk = RiffChunk::new(ChunkType::Ixml, b"<BWFXML><PROJECT>".to_vec());
+        assert!(IxmlChunk::from_chunk(&chunk).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::{utils, ChunkType, RiffChunk, WavTagError};
+
+/// A sub-chunk of a LIST chunk as its tag and unpadded data.
+pub(crate) type SubChunk = ([u8; 4], Vec<u8>);
+
+/// Split the body of a LIST chunk into its form type and the (unpadded) sub-chunks that follow.
+pub(crate) fn read_list(chunk: &RiffChunk) -> Result<([u8; 4], Vec<SubChunk>), WavTagError> {
+    if chunk.header != ChunkType::List {
+        return Err(WavTagError::wrong_chunk(ChunkType::List, chunk));
+    };
+
+    read_list_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+}
+
+fn read_list_body(body: &[u8]) -> Result<([u8; 4], Vec<SubChunk>), io::Error> {
+    let mut data = Cursor::new(body);
+    let mut list_type = [0u8; 4];
+    data.read_exact(&mut list_type)?;
+
+    let mut sub_chunks = Vec::new();
+
+    // a trailing pad byte on the LIST chunk itself is not a sub-chunk.
+    while data.position() + 8 <= body.len() as u64 {
+        let mut tag = [0u8; 4];
+        data.read_exact(&mut tag)?;
+        let len = data.read_u32::<LittleEndian>()?;
+
+        let mut sub_chunk = vec![0u8; len as usize];
+        data.read_exact(&mut sub_chunk)?;
+
+        // sub-chunks are word aligned, but the final pad byte is sometimes left off.
+        if utils::padded_size(len) != len && data.position() < body.len() as u64 {
+            data.set_position(data.position() + 1);
+        }
+
+        sub_chunks.push((tag, sub_chunk));
+    }
+
+    Ok((list_type, sub_chunks))
+}
+
+/// Build a LIST chunk from a form type and its sub-chunks, padding each sub-chunk to an even size.
+pub(crate) fn write_list(list_type: [u8; 4], sub_chunks: &[SubChunk]) -> RiffChunk {
+    let mut data = list_type.to_vec();
+
+    for (tag, sub_chunk) in sub_chunks.iter() {
+        data.extend_from_slice(tag);
+        data.write_u32::<LittleEndian>(sub_chunk.len() as u32)
+            .unwrap(); // writes to a vec are infallible.
+        data.extend_from_slice(sub_chunk);
+        utils::pad_vec(
+            &mut data,
+            (utils::padded_size(sub_chunk.len() as u32) as usize) - sub_chunk.len(),
+        );
+    }
+
+    RiffChunk::new(ChunkType::List, data)
+}
+
+/// Read a null terminated (ZSTR) string, stopping at the first null byte if there is one.
+pub(crate) fn read_zstr(data: &[u8]) -> String {
+    let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..end]).into_owned()
+}
+
+/// Write a string with its null terminator.
+pub(crate) fn write_zstr(data: &mut Vec<u8>, text: &str) {
+    data.extend_from_slice(text.as_bytes());
+    data.push(0);
+}
@@ -2,4 +2,44 @@ mod inst;
 pub use self::inst::InstrumentChunk;
 
 mod smpl;
-pub use self::smpl::{ SamplerChunk, SampleLoop, LoopType };
+pub use self::smpl::{LoopType, SampleLoop, SamplerChunk};
+
+mod cue;
+pub use self::cue::{CueChunk, CuePoint};
+
+mod list;
+
+mod adtl;
+pub use self::adtl::{AssociatedData, AssociatedDataChunk, LabelledText};
+
+mod info;
+pub use self::info::{
+    InfoChunk, INFO_ARTIST, INFO_COMMENT, INFO_COPYRIGHT, INFO_CREATION_DATE, INFO_GENRE,
+    INFO_KEYWORDS, INFO_SOFTWARE, INFO_TITLE,
+};
+
+mod fmt;
+pub use self::fmt::{FormatTag, WaveFormat, WaveFormatExtensible};
+
+mod codecs;
+
+mod data;
+pub use self::data::{AudioData, Samples};
+
+mod acid;
+pub use self::acid::AcidChunk;
+
+mod bext;
+pub use self::bext::BextChunk;
+
+mod ixml;
+pub use self::ixml::{IxmlChunk, IxmlTrack};
+
+mod clm;
+pub use self::clm::WavetableChunk;
+
+mod id3;
+pub use self::id3::{
+    Id3Chunk, Id3Frame, ID3_ARTIST, ID3_BPM, ID3_COMMENT, ID3_COPYRIGHT, ID3_GENRE,
+    ID3_INFO_FIELDS, ID3_KEY, ID3_RECORDING_TIME, ID3_SOFTWARE, ID3_TITLE, ID3_YEAR,
+};
@@ -1,11 +1,11 @@
 use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
 
 use std::io;
-use std::io::{Cursor, Error, ErrorKind};
+use std::io::{Cursor, Read};
 
-use crate::{ChunkType, RiffChunk, RiffFile};
+use crate::{ChunkType, RiffChunk, RiffFile, WavTagError};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct SamplerChunk {
     /// The manufacturer field specifies the MIDI Manufacturer's Association (MMA) Manufacturer
     /// code for the sampler intended to receive this file's waveform. Each manufacturer of a
@@ -48,9 +48,9 @@ pub struct SamplerChunk {
     pub sample_loops: Vec<SampleLoop>,
 
     /// Sampler Data
-    /// The sampler data value specifies the number of bytes that will follow this chunk (including
-    /// the entire sample loop list). This value is greater than 0 when an application needs to save
-    /// additional information. This value is reflected in this chunks data size value.
+    /// Sampler specific data stored after the sample loop list. Its length is written as the
+    /// sampler data field, which is greater than 0 when an application needs to save additional
+    /// information. This value is reflected in this chunks data size value.
     pub sampler_data: Vec<u8>,
 }
 
@@ -81,41 +81,50 @@ pub struct SampleLoop {
     pub play_count: u32,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum LoopType {
     Forward,
     PingPong,
     Reverse,
+    /// Reserved (3 - 31) or sampler specific (32 and above) loop types.
+    Other(u32),
 }
 
-impl Default for SamplerChunk {
-    fn default() -> Self {
-        Self {
-            manufacturer: 0,
-            product: 0,
-            sample_period: 0,
-            midi_unity_note: 0,
-            midi_pitch_fraction: 0,
-            smpte_format: 0,
-            smpte_offset: 0,
-            sample_loops: Vec::new(),
-            sampler_data: Vec::new(),
+impl From<u32> for LoopType {
+    fn from(loop_type: u32) -> Self {
+        match loop_type {
+            0 => LoopType::Forward,
+            1 => LoopType::PingPong,
+            2 => LoopType::Reverse,
+            other => LoopType::Other(other),
+        }
+    }
+}
+
+impl From<LoopType> for u32 {
+    fn from(loop_type: LoopType) -> Self {
+        match loop_type {
+            LoopType::Forward => 0,
+            LoopType::PingPong => 1,
+            LoopType::Reverse => 2,
+            LoopType::Other(other) => other,
         }
     }
 }
 
 impl SamplerChunk {
-    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, io::Error> {
+    pub fn from_chunk(chunk: &RiffChunk) -> Result<Self, WavTagError> {
         if chunk.header != ChunkType::Sampler {
-            return Err(Error::new(
-                ErrorKind::Other,
-                "attempted from_chunk() on non-sampler chunk",
-            ));
+            return Err(WavTagError::wrong_chunk(ChunkType::Sampler, chunk));
         };
 
-        let mut data = Cursor::new(&chunk.data);
+        Self::read_body(&chunk.data).map_err(|e| WavTagError::from_body(chunk, e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
 
-        Ok(SamplerChunk {
+        let mut smpl = SamplerChunk {
             manufacturer: data.read_u32::<LittleEndian>()?,
             product: data.read_u32::<LittleEndian>()?,
             sample_period: data.read_u32::<LittleEndian>()?,
@@ -123,78 +132,132 @@ impl SamplerChunk {
             midi_pitch_fraction: data.read_u32::<LittleEndian>()?,
             smpte_format: data.read_u32::<LittleEndian>()?,
             smpte_offset: data.read_u32::<LittleEndian>()?,
-            sample_loops: {
-                let num_sample_loops = data.read_u32::<LittleEndian>()?;
-                let _ = data.read_u32::<LittleEndian>()?; // sampler_data_chunk_size
-
-                (0..num_sample_loops)
-                    .map(|_| SampleLoop {
-                        id: data.read_u32::<LittleEndian>().unwrap(),
-                        loop_type: {
-                            let lt = data.read_u32::<LittleEndian>().unwrap();
-                            match lt {
-                                // TODO: other loop types!
-                                _ => LoopType::Forward,
-                            }
-                        },
-                        start: data.read_u32::<LittleEndian>().unwrap(),
-                        end: data.read_u32::<LittleEndian>().unwrap(),
-                        fraction: data.read_u32::<LittleEndian>().unwrap(),
-                        play_count: data.read_u32::<LittleEndian>().unwrap(),
-                    })
-                    .collect()
-            },
-            sampler_data: Vec::new(),
-        })
+            ..Default::default()
+        };
+
+        let num_sample_loops = data.read_u32::<LittleEndian>()?;
+        let sampler_data_len = data.read_u32::<LittleEndian>()?;
+
+        smpl.sample_loops = (0..num_sample_loops)
+            .map(|_| {
+                Ok(SampleLoop {
+                    id: data.read_u32::<LittleEndian>()?,
+                    loop_type: LoopType::from(data.read_u32::<LittleEndian>()?),
+                    start: data.read_u32::<LittleEndian>()?,
+                    end: data.read_u32::<LittleEndian>()?,
+                    fraction: data.read_u32::<LittleEndian>()?,
+                    play_count: data.read_u32::<LittleEndian>()?,
+                })
+            })
+            .collect::<Result<Vec<SampleLoop>, io::Error>>()?;
+
+        // sampler specific data follows the loops. a short chunk keeps whatever is there.
+        data.take(sampler_data_len as u64)
+            .read_to_end(&mut smpl.sampler_data)?;
+
+        Ok(smpl)
     }
 
     pub fn serialise(&self) -> Vec<u8> {
-        let mut chunk = Vec::with_capacity(36 + 24); // space for static fields and sample_loops
-        unsafe { chunk.set_len(36 + 24) }; // todo: find a safe way to zero the elements.
-
-        let sample_loop = self
-            .sample_loops
-            .first()
-            .expect("sampler chunk to have at least one sample loop");
-        let _ = 0; // sampler_data: greater than 0 if extra sampler data is present.
-
-        LittleEndian::write_u32_into(
-            &vec![
-                self.manufacturer,
-                self.product,
-                self.sample_period,
-                self.midi_unity_note,
-                self.midi_pitch_fraction,
-                self.smpte_format,
-                self.smpte_offset,
-                1_u32,
-                0,
+        let mut fields = vec![
+            self.manufacturer,
+            self.product,
+            self.sample_period,
+            self.midi_unity_note,
+            self.midi_pitch_fraction,
+            self.smpte_format,
+            self.smpte_offset,
+            self.sample_loops.len() as u32,
+            self.sampler_data.len() as u32,
+        ];
+
+        for sample_loop in self.sample_loops.iter() {
+            fields.extend_from_slice(&[
                 sample_loop.id,
-                0_u32,
+                u32::from(sample_loop.loop_type),
                 sample_loop.start,
                 sample_loop.end,
                 sample_loop.fraction,
                 sample_loop.play_count,
-            ],
-            &mut chunk,
-        );
+            ]);
+        }
+
+        let mut chunk = vec![0; fields.len() * 4];
+        LittleEndian::write_u32_into(&fields, &mut chunk);
+        chunk.extend_from_slice(&self.sampler_data);
 
         chunk
     }
 }
 
 impl RiffFile {
-    pub fn get_sampler_chunk(&self) -> SamplerChunk {
+    pub fn get_sampler_chunk(&self) -> Result<SamplerChunk, WavTagError> {
         match self.find_chunk_by_type(ChunkType::Sampler) {
-            Some(c) => SamplerChunk::from_chunk(c).expect("chunk to be a valid sampler chunk"),
-            None => SamplerChunk::default(),
+            Some(c) => SamplerChunk::from_chunk(&self.load_chunk(c)?),
+            None => Ok(SamplerChunk::default()),
         }
     }
 
     pub fn set_sampler_chunk(&mut self, chunk: SamplerChunk) {
-        self.add_or_replace_chunk_by_type(RiffChunk {
-            header: ChunkType::Sampler,
-            data: chunk.serialise(),
-        });
+        self.add_or_replace_chunk_by_type(RiffChunk::new(ChunkType::Sampler, chunk.serialise()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_loops_and_sampler_data() {
+        let loop_types = [
+            LoopType::Forward,
+            LoopType::PingPong,
+            LoopType::Reverse,
+            LoopType::Other(40),
+        ];
+        let smpl = SamplerChunk {
+            sample_period: 22675,
+            midi_unity_note: 45,
+            midi_pitch_fraction: 0x8000_0000,
+            sample_loops: loop_types
+                .iter()
+                .enumerate()
+                .map(|(i, &loop_type)| SampleLoop {
+                    id: i as u32 + 1,
+                    loop_type,
+                    start: i as u32 * 100,
+                    end: i as u32 * 100 + 99,
+                    fraction: 0,
+                    play_count: i as u32,
+                })
+                .collect(),
+            sampler_data: vec![1, 2, 3],
+            ..SamplerChunk::default()
+        };
+
+        let body = smpl.serialise();
+        assert_eq!(body.len(), 36 + 4 * 24 + 3);
+        assert_eq!(body[28..32], 4u32.to_le_bytes());
+        assert_eq!(body[32..36], 3u32.to_le_bytes());
+        // the type of the last loop.
+        assert_eq!(body[36 + 3 * 24 + 4..36 + 3 * 24 + 8], 40u32.to_le_bytes());
+        assert_eq!(body[body.len() - 3..], [1, 2, 3]);
+
+        let chunk = RiffChunk::new(ChunkType::Sampler, body);
+        assert_eq!(SamplerChunk::from_chunk(&chunk).unwrap(), smpl);
+    }
+
+    #[test]
+    fn keeps_short_sampler_data() {
+        let smpl = SamplerChunk {
+            sampler_data: vec![1, 2, 3, 4],
+            ..SamplerChunk::default()
+        };
+        let mut body = smpl.serialise();
+        body.truncate(body.len() - 2);
+
+        let chunk = RiffChunk::new(ChunkType::Sampler, body);
+        let read = SamplerChunk::from_chunk(&chunk).unwrap();
+        assert_eq!(read.sampler_data, [1, 2]);
     }
 }
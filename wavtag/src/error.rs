@@ -0,0 +1,180 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::{ChunkType, RiffChunk};
+
+/// Everything that can go wrong reading, parsing or writing a file. Offsets are from the start
+/// of the file.
+#[derive(Debug)]
+pub enum WavTagError {
+    /// An error from the underlying reader or writer.
+    Io(io::Error),
+
+    /// The file doesn't start with the expected tag (eg. `RIFF` or `WAVE`).
+    BadMagic {
+        offset: u64,
+        expected: &'static str,
+        found: [u8; 4],
+    },
+
+    /// The file ends part way through a chunk.
+    TruncatedChunk {
+        offset: u64,
+        tag: [u8; 4],
+        size: u64,
+        available: u64,
+    },
+
+    /// A chunk size field that can't be resolved, eg. 0xFFFFFFFF with no matching ds64 entry.
+    InvalidChunkSize {
+        offset: u64,
+        tag: [u8; 4],
+        size: u64,
+    },
+
+    /// A chunk whose body couldn't be parsed. The offset is known when the chunk was read from a
+    /// file.
+    MalformedChunk {
+        chunk: ChunkType,
+        offset: Option<u64>,
+        reason: String,
+    },
+
+    /// `from_chunk()` was called on a chunk of another type.
+    WrongChunkType {
+        expected: ChunkType,
+        found: ChunkType,
+    },
+
+    /// A chunk required for the operation is missing (eg. `fmt ` when decoding samples).
+    MissingChunk(ChunkType),
+
+    /// A field holds a value outside the range allowed by the spec.
+    InvalidValue { field: &'static str, reason: String },
+
+    /// The operation isn't supported for this file (eg. an unknown sample format).
+    Unsupported(String),
+
+    /// Compressed audio that couldn't be decoded (eg. a corrupt FLAC frame).
+    Decode(String),
+}
+
+pub(crate) fn tag_name(tag: &[u8; 4]) -> String {
+    String::from_utf8_lossy(tag).into_owned()
+}
+
+impl WavTagError {
+    pub(crate) fn malformed(chunk: &RiffChunk, reason: impl fmt::Display) -> Self {
+        WavTagError::MalformedChunk {
+            chunk: chunk.header.clone(),
+            offset: chunk.offset,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// An error from reading the body of a chunk, where running out of bytes means the chunk is
+    /// too short for its contents.
+    pub(crate) fn from_body(chunk: &RiffChunk, e: io::Error) -> Self {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            WavTagError::malformed(chunk, "chunk is too short")
+        } else {
+            WavTagError::malformed(chunk, e)
+        }
+    }
+
+    pub(crate) fn wrong_chunk(expected: ChunkType, chunk: &RiffChunk) -> Self {
+        WavTagError::WrongChunkType {
+            expected,
+            found: chunk.header.clone(),
+        }
+    }
+}
+
+impl fmt::Display for WavTagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WavTagError::Io(e) => write!(f, "{}", e),
+            WavTagError::BadMagic {
+                offset,
+                expected,
+                found,
+            } => write!(
+                f,
+                "expected {} at offset {}, found {:?}",
+                expected,
+                offset,
+                tag_name(found)
+            ),
+            WavTagError::TruncatedChunk {
+                offset,
+                tag,
+                size,
+                available,
+            } => write!(
+                f,
+                "{:?} chunk at offset {} is {} bytes, but only {} bytes remain",
+                tag_name(tag),
+                offset,
+                size,
+                available
+            ),
+            WavTagError::InvalidChunkSize { offset, tag, size } => write!(
+                f,
+                "{:?} chunk at offset {} has an invalid size of {}",
+                tag_name(tag),
+                offset,
+                size
+            ),
+            WavTagError::MalformedChunk {
+                chunk,
+                offset,
+                reason,
+            } => {
+                write!(f, "malformed {:?} chunk", tag_name(&chunk.clone().to_tag()))?;
+                if let Some(offset) = offset {
+                    write!(f, " at offset {}", offset)?;
+                }
+                write!(f, ": {}", reason)
+            }
+            WavTagError::WrongChunkType { expected, found } => write!(
+                f,
+                "expected {:?} chunk, found {:?}",
+                tag_name(&expected.clone().to_tag()),
+                tag_name(&found.clone().to_tag())
+            ),
+            WavTagError::MissingChunk(chunk) => {
+                write!(f, "no {:?} chunk found", tag_name(&chunk.clone().to_tag()))
+            }
+            WavTagError::InvalidValue { field, reason } => {
+                write!(f, "invalid {}: {}", field, reason)
+            }
+            WavTagError::Unsupported(reason) => write!(f, "{}", reason),
+            WavTagError::Decode(reason) => write!(f, "couldn't decode audio: {}", reason),
+        }
+    }
+}
+
+impl Error for WavTagError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WavTagError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WavTagError {
+    fn from(e: io::Error) -> Self {
+        WavTagError::Io(e)
+    }
+}
+
+impl From<WavTagError> for io::Error {
+    fn from(e: WavTagError) -> Self {
+        match e {
+            WavTagError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
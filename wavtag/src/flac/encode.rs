@@ -0,0 +1,468 @@
+/// The number of samples per channel in each frame, the default of the reference encoder.
+pub(crate) const BLOCK_SIZE: usize = 4096;
+
+/// Rice parameters are 4 bits, with 15 reserved as an escape code.
+const MAX_RICE_PARAMETER: u32 = 14;
+
+const MAX_PARTITION_ORDER: u32 = 8;
+
+/// The highest order of the fixed predictors.
+const MAX_FIXED_ORDER: usize = 4;
+
+/// The frames of an encoded stream, along with the sizes needed for the stream info block.
+pub(crate) struct EncodedFrames {
+    pub data: Vec<u8>,
+    pub min_frame_size: u32,
+    pub max_frame_size: u32,
+}
+
+struct BitWriter {
+    data: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            data: Vec::new(),
+            acc: 0,
+            bits: 0,
+        }
+    }
+
+    /// Write the low `bits` bits of `value`, most significant first. At most 32 bits at a time.
+    fn write(&mut self, value: u64, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+
+        self.acc = (self.acc << bits) | (value & ((1 << bits) - 1));
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.data.push((self.acc >> self.bits) as u8);
+        }
+        self.acc &= (1 << self.bits) - 1;
+    }
+
+    /// Write a two's complement value in `bits` bits.
+    fn write_signed(&mut self, value: i64, bits: u32) {
+        self.write(value as u64, bits);
+    }
+
+    /// Write `zeros` 0 bits followed by a 1.
+    fn write_unary(&mut self, mut zeros: u64) {
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as u32 + 1);
+    }
+
+    fn write_rice(&mut self, residual: i64, parameter: u32) {
+        let folded = fold(residual);
+        self.write_unary(folded >> parameter);
+        self.write(folded, parameter);
+    }
+
+    /// Pad with 0 bits to the next byte boundary.
+    fn align(&mut self) {
+        if self.bits > 0 {
+            self.write(0, 8 - self.bits);
+        }
+    }
+
+    /// Write a frame number in the extended UTF-8 coding FLAC uses for frame headers.
+    fn write_utf8(&mut self, value: u64) {
+        if value < 0x80 {
+            self.write(value, 8);
+            return;
+        }
+
+        // each continuation byte holds 6 bits, the first byte what is left after its prefix.
+        let mut len = 2;
+        while len < 7 && value >= 1 << (5 * len + 1) {
+            len += 1;
+        }
+
+        let prefix = (0xff00 >> len) & 0xff;
+        self.write(prefix | (value >> (6 * (len - 1))), 8);
+        for i in (0..len - 1).rev() {
+            self.write(0x80 | ((value >> (6 * i)) & 0x3f), 8);
+        }
+    }
+}
+
+/// Map signed residuals to unsigned for Rice coding: 0, -1, 1, -2, 2...
+fn fold(residual: i64) -> u64 {
+    if residual >= 0 {
+        (residual as u64) << 1
+    } else {
+        ((-residual as u64) << 1) - 1
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+enum SubframeKind {
+    Constant,
+    Verbatim,
+    Fixed {
+        order: usize,
+        partition_order: u32,
+        parameters: Vec<u32>,
+    },
+}
+
+/// One channel of a frame, planned but not yet written.
+struct Subframe {
+    kind: SubframeKind,
+
+    /// Low bits that are 0 in every sample, which are shifted out.
+    wasted_bits: u32,
+
+    /// Bits per sample after the wasted bits are removed.
+    bits_per_sample: u32,
+
+    samples: Vec<i64>,
+    residual: Vec<i64>,
+
+    /// The estimated size of the subframe in bits.
+    size: u64,
+}
+
+/// The residual of the fixed polynomial predictor of the given order. The first `order` values
+/// are the warm up samples rather than residuals.
+fn fixed_residual(samples: &[i64], order: usize) -> Vec<i64> {
+    let mut residual = samples.to_vec();
+    for o in 0..order {
+        for i in (o + 1..residual.len()).rev() {
+            residual[i] -= residual[i - 1];
+        }
+    }
+    residual
+}
+
+/// Choose the partition order and Rice parameters for a residual, returning the estimated size
+/// in bits along with them.
+fn plan_partitions(residual: &[i64], order: usize) -> (u64, u32, Vec<u32>) {
+    let block_size = residual.len();
+
+    // partitions must divide the block evenly and the first must hold more than the warm up.
+    let mut max_order = 0;
+    while max_order < MAX_PARTITION_ORDER
+        && block_size.is_multiple_of(1 << (max_order + 1))
+        && block_size >> (max_order + 1) > order
+    {
+        max_order += 1;
+    }
+
+    // sums of the folded residuals for the finest partitions, merged pairwise for coarser ones.
+    let partition_len = block_size >> max_order;
+    let mut sums: Vec<(u64, u64)> = (0..1 << max_order)
+        .map(|p| {
+            let start = if p == 0 { order } else { p * partition_len };
+            let values = &residual[start..(p + 1) * partition_len];
+            (values.len() as u64, values.iter().map(|&r| fold(r)).sum())
+        })
+        .collect();
+
+    let mut best: Option<(u64, u32, Vec<u32>)> = None;
+    for partition_order in (0..=max_order).rev() {
+        let mut size = 0;
+        let mut parameters = Vec::with_capacity(sums.len());
+        for &(count, sum) in sums.iter() {
+            let (bits, parameter) = (0..=MAX_RICE_PARAMETER)
+                .map(|k| (count * (k as u64 + 1) + (sum >> k), k))
+                .min()
+                .expect("a rice parameter");
+            size += 4 + bits;
+            parameters.push(parameter);
+        }
+
+        if best.as_ref().is_none_or(|b| size < b.0) {
+            best = Some((size, partition_order, parameters));
+        }
+
+        sums = sums
+            .chunks(2)
+            .map(|pair| pair.iter().fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1)))
+            .collect();
+    }
+
+    best.expect("at least one partition order")
+}
+
+fn plan_subframe(samples: Vec<i64>, bits_per_sample: u32) -> Subframe {
+    let block_size = samples.len();
+
+    if samples.iter().all(|&s| s == samples[0]) {
+        return Subframe {
+            kind: SubframeKind::Constant,
+            wasted_bits: 0,
+            bits_per_sample,
+            samples,
+            residual: Vec::new(),
+            size: 8 + bits_per_sample as u64,
+        };
+    }
+
+    let wasted_bits = samples.iter().fold(0, |acc, &s| acc | s).trailing_zeros();
+    let samples: Vec<i64> = samples.iter().map(|&s| s >> wasted_bits).collect();
+    let bits_per_sample = bits_per_sample - wasted_bits;
+    let header_size = 8 + wasted_bits as u64;
+
+    let mut best = Subframe {
+        kind: SubframeKind::Verbatim,
+        wasted_bits,
+        bits_per_sample,
+        samples: Vec::new(),
+        residual: Vec::new(),
+        size: header_size + block_size as u64 * bits_per_sample as u64,
+    };
+
+    for order in 0..=MAX_FIXED_ORDER.min(block_size - 1) {
+        let residual = fixed_residual(&samples, order);
+        let (residual_size, partition_order, parameters) = plan_partitions(&residual, order);
+        let size = header_size + order as u64 * bits_per_sample as u64 + 6 + residual_size;
+
+        if size < best.size {
+            best = Subframe {
+                kind: SubframeKind::Fixed {
+                    order,
+                    partition_order,
+                    parameters,
+                },
+                wasted_bits,
+                bits_per_sample,
+                samples: Vec::new(),
+                residual,
+                size,
+            };
+        }
+    }
+
+    best.samples = samples;
+    best
+}
+
+fn write_subframe(writer: &mut BitWriter, subframe: &Subframe) {
+    let subframe_type = match subframe.kind {
+        SubframeKind::Constant => 0,
+        SubframeKind::Verbatim => 1,
+        SubframeKind::Fixed { order, .. } => 8 | order as u64,
+    };
+
+    writer.write(0, 1);
+    writer.write(subframe_type, 6);
+    if subframe.wasted_bits > 0 {
+        writer.write(1, 1);
+        writer.write_unary(subframe.wasted_bits as u64 - 1);
+    } else {
+        writer.write(0, 1);
+    }
+
+    let bits_per_sample = subframe.bits_per_sample;
+    match &subframe.kind {
+        SubframeKind::Constant => writer.write_signed(subframe.samples[0], bits_per_sample),
+        SubframeKind::Verbatim => {
+            for &sample in subframe.samples.iter() {
+                writer.write_signed(sample, bits_per_sample);
+            }
+        }
+        SubframeKind::Fixed {
+            order,
+            partition_order,
+            parameters,
+        } => {
+            for &sample in subframe.samples[..*order].iter() {
+                writer.write_signed(sample, bits_per_sample);
+            }
+
+            // partitioned Rice coding with 4-bit parameters.
+            writer.write(0, 2);
+            writer.write(*partition_order as u64, 4);
+
+            let partition_len = subframe.residual.len() >> partition_order;
+            for (p, &parameter) in parameters.iter().enumerate() {
+                let start = if p == 0 { *order } else { p * partition_len };
+                writer.write(parameter as u64, 4);
+                for &residual in subframe.residual[start..(p + 1) * partition_len].iter() {
+                    writer.write_rice(residual, parameter);
+                }
+            }
+        }
+    }
+}
+
+/// The frame header code for common sample rates, or 0 to take it from the stream info.
+fn sample_rate_code(sample_rate: u32) -> u64 {
+    match sample_rate {
+        88200 => 1,
+        176400 => 2,
+        192000 => 3,
+        8000 => 4,
+        16000 => 5,
+        22050 => 6,
+        24000 => 7,
+        32000 => 8,
+        44100 => 9,
+        48000 => 10,
+        96000 => 11,
+        _ => 0,
+    }
+}
+
+fn sample_size_code(bits_per_sample: u32) -> u64 {
+    match bits_per_sample {
+        8 => 1,
+        12 => 2,
+        16 => 4,
+        20 => 5,
+        24 => 6,
+        _ => 0,
+    }
+}
+
+/// Encode one frame from a block of samples per channel.
+fn encode_frame(
+    channels: &[Vec<i64>],
+    frame_number: u64,
+    sample_rate: u32,
+    bits_per_sample: u32,
+) -> Vec<u8> {
+    let block_size = channels[0].len();
+
+    // stereo is also tried as a side channel (which needs an extra bit) with the left, right or
+    // mid channel, keeping whichever is smallest.
+    let (channel_assignment, subframes) = if channels.len() == 2 {
+        let (left, right) = (&channels[0], &channels[1]);
+        let side: Vec<i64> = left.iter().zip(right).map(|(l, r)| l - r).collect();
+        let mid: Vec<i64> = left.iter().zip(right).map(|(l, r)| (l + r) >> 1).collect();
+
+        let left = plan_subframe(left.clone(), bits_per_sample);
+        let right = plan_subframe(right.clone(), bits_per_sample);
+        let side = plan_subframe(side, bits_per_sample + 1);
+        let mid = plan_subframe(mid, bits_per_sample);
+
+        let independent = left.size + right.size;
+        let left_side = left.size + side.size;
+        let right_side = side.size + right.size;
+        let mid_side = mid.size + side.size;
+        let smallest = independent.min(left_side).min(right_side).min(mid_side);
+
+        if smallest == independent {
+            (1, vec![left, right])
+        } else if smallest == left_side {
+            (8, vec![left, side])
+        } else if smallest == right_side {
+            (9, vec![side, right])
+        } else {
+            (10, vec![mid, side])
+        }
+    } else {
+        (
+            channels.len() as u64 - 1,
+            channels
+                .iter()
+                .map(|c| plan_subframe(c.clone(), bits_per_sample))
+                .collect(),
+        )
+    };
+
+    let mut writer = BitWriter::new();
+
+    // sync code, reserved bit and fixed block size strategy.
+    writer.write(0b11_1111_1111_1110, 14);
+    writer.write(0, 2);
+    if block_size == BLOCK_SIZE {
+        writer.write(12, 4);
+    } else {
+        writer.write(7, 4); // 16-bit block size at the end of the header
+    }
+    writer.write(sample_rate_code(sample_rate), 4);
+    writer.write(channel_assignment, 4);
+    writer.write(sample_size_code(bits_per_sample), 3);
+    writer.write(0, 1);
+    writer.write_utf8(frame_number);
+    if block_size != BLOCK_SIZE {
+        writer.write(block_size as u64 - 1, 16);
+    }
+    let crc = crc8(&writer.data);
+    writer.write(crc as u64, 8);
+
+    for subframe in subframes.iter() {
+        write_subframe(&mut writer, subframe);
+    }
+
+    writer.align();
+    let crc = crc16(&writer.data);
+    writer.write(crc as u64, 16);
+
+    writer.data
+}
+
+/// Encode interleaved samples as FLAC frames, using the fixed predictors.
+pub(crate) fn encode(
+    samples: &[i32],
+    channels: usize,
+    sample_rate: u32,
+    bits_per_sample: u32,
+) -> EncodedFrames {
+    let mut encoded = EncodedFrames {
+        data: Vec::new(),
+        min_frame_size: u32::MAX,
+        max_frame_size: 0,
+    };
+
+    for (frame_number, block) in samples.chunks(BLOCK_SIZE * channels).enumerate() {
+        let block: Vec<Vec<i64>> = (0..channels)
+            .map(|c| {
+                block
+                    .iter()
+                    .skip(c)
+                    .step_by(channels)
+                    .map(|&s| s as i64)
+                    .collect()
+            })
+            .collect();
+
+        let frame = encode_frame(&block, frame_number as u64, sample_rate, bits_per_sample);
+        encoded.min_frame_size = encoded.min_frame_size.min(frame.len() as u32);
+        encoded.max_frame_size = encoded.max_frame_size.max(frame.len() as u32);
+        encoded.data.extend_from_slice(&frame);
+    }
+
+    if encoded.data.is_empty() {
+        encoded.min_frame_size = 0;
+    }
+
+    encoded
+}
@@ -0,0 +1,653 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read, Write};
+
+use crate::riff::header_to_rifftype;
+use crate::{
+    AudioData, ChunkType, FormatTag, RiffChunk, RiffFile, RiffForm, SampleMetadata, Samples,
+    VorbisComments, WavTagError, WaveFormat,
+};
+
+mod encode;
+
+mod streaminfo;
+pub use self::streaminfo::StreamInfo;
+
+/// The application id of the blocks holding the chunks of the original WAV, as written by
+/// `flac --keep-foreign-metadata`.
+const RIFF_APPLICATION_ID: [u8; 4] = *b"riff";
+
+/// Metadata blocks store their length in 24 bits.
+const MAX_BLOCK_LEN: usize = (1 << 24) - 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlacBlockType {
+    StreamInfo,
+    Padding,
+    Application,
+    SeekTable,
+    VorbisComment,
+    CueSheet,
+    Picture,
+    Other(u8),
+}
+
+impl From<u8> for FlacBlockType {
+    fn from(block_type: u8) -> Self {
+        match block_type {
+            0 => FlacBlockType::StreamInfo,
+            1 => FlacBlockType::Padding,
+            2 => FlacBlockType::Application,
+            3 => FlacBlockType::SeekTable,
+            4 => FlacBlockType::VorbisComment,
+            5 => FlacBlockType::CueSheet,
+            6 => FlacBlockType::Picture,
+            other => FlacBlockType::Other(other),
+        }
+    }
+}
+
+impl From<FlacBlockType> for u8 {
+    fn from(block_type: FlacBlockType) -> Self {
+        match block_type {
+            FlacBlockType::StreamInfo => 0,
+            FlacBlockType::Padding => 1,
+            FlacBlockType::Application => 2,
+            FlacBlockType::SeekTable => 3,
+            FlacBlockType::VorbisComment => 4,
+            FlacBlockType::CueSheet => 5,
+            FlacBlockType::Picture => 6,
+            FlacBlockType::Other(other) => other,
+        }
+    }
+}
+
+/// A metadata block of a FLAC file.
+pub struct FlacBlock {
+    pub block_type: FlacBlockType,
+    pub data: Vec<u8>,
+
+    /// Where the block header was found in the file it was read from.
+    pub offset: Option<u64>,
+}
+
+impl FlacBlock {
+    pub fn new(block_type: FlacBlockType, data: Vec<u8>) -> Self {
+        FlacBlock {
+            block_type,
+            data,
+            offset: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The id of an application block, which is the first four bytes of its data.
+    pub fn application_id(&self) -> Option<[u8; 4]> {
+        match self.block_type {
+            FlacBlockType::Application => self.data.get(..4)?.try_into().ok(),
+            _ => None,
+        }
+    }
+
+    fn chunk_type(&self) -> ChunkType {
+        ChunkType::Unknown(format!("{:?}", self.block_type))
+    }
+
+    pub(crate) fn check_type(&self, expected: FlacBlockType) -> Result<(), WavTagError> {
+        if self.block_type != expected {
+            return Err(WavTagError::WrongChunkType {
+                expected: ChunkType::Unknown(format!("{:?}", expected)),
+                found: self.chunk_type(),
+            });
+        }
+        Ok(())
+    }
+
+    pub(crate) fn malformed(&self, reason: impl std::fmt::Display) -> WavTagError {
+        WavTagError::MalformedChunk {
+            chunk: self.chunk_type(),
+            offset: self.offset,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// See `WavTagError::from_body`.
+    pub(crate) fn body_error(&self, e: io::Error) -> WavTagError {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            self.malformed("block is too short")
+        } else {
+            self.malformed(e)
+        }
+    }
+}
+
+fn decode_error(e: claxon::Error) -> WavTagError {
+    match e {
+        claxon::Error::IoError(e) => WavTagError::Io(e),
+        claxon::Error::FormatError(reason) => WavTagError::Decode(reason.to_string()),
+        claxon::Error::Unsupported(reason) => WavTagError::Unsupported(reason.to_string()),
+    }
+}
+
+pub struct FlacFile {
+    pub filename: String,
+    pub blocks: Vec<FlacBlock>,
+
+    /// The encoded audio frames that follow the metadata blocks.
+    pub frames: Vec<u8>,
+}
+
+impl FlacFile {
+    pub fn read<R: Read>(mut reader: R, filename: String) -> Result<Self, WavTagError> {
+        let mut offset = 0;
+        let mut tag = [0u8; 4];
+        reader.read_exact(&mut tag)?;
+
+        // some taggers put an ID3v2 tag before the stream, which is skipped.
+        if tag.starts_with(b"ID3") {
+            let mut header = [0u8; 6];
+            reader.read_exact(&mut header)?;
+            let size = header[2..]
+                .iter()
+                .fold(0u64, |acc, &b| acc << 7 | (b & 0x7f) as u64);
+            io::copy(&mut (&mut reader).take(size), &mut io::sink())?;
+            offset = 10 + size;
+            reader.read_exact(&mut tag)?;
+        }
+
+        if &tag != b"fLaC" {
+            return Err(WavTagError::BadMagic {
+                offset,
+                expected: "fLaC",
+                found: tag,
+            });
+        }
+        offset += 4;
+
+        let mut blocks = Vec::new();
+
+        loop {
+            let header = reader.read_u32::<BigEndian>()?;
+            let last = header & 0x8000_0000 != 0;
+            let len = header as usize & MAX_BLOCK_LEN;
+
+            let data = crate::utils::read_bytes(&mut reader, len)?;
+            if data.len() < len {
+                return Err(WavTagError::TruncatedChunk {
+                    offset,
+                    tag: *b"fLaC",
+                    size: len as u64,
+                    available: data.len() as u64,
+                });
+            }
+
+            let mut block = FlacBlock::new(((header >> 24) as u8 & 0x7f).into(), data);
+            block.offset = Some(offset);
+            blocks.push(block);
+
+            offset += 4 + len as u64;
+            if last {
+                break;
+            }
+        }
+
+        let mut frames = Vec::new();
+        reader.read_to_end(&mut frames)?;
+
+        Ok(FlacFile {
+            filename,
+            blocks,
+            frames,
+        })
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), WavTagError> {
+        writer.write_all(b"fLaC")?;
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            if block.len() > MAX_BLOCK_LEN {
+                return Err(WavTagError::InvalidValue {
+                    field: "metadata block",
+                    reason: format!("{} bytes is too large for a FLAC block", block.len()),
+                });
+            }
+
+            let last = if index + 1 == self.blocks.len() {
+                0x8000_0000
+            } else {
+                0
+            };
+            let block_type: u8 = block.block_type.into();
+            writer.write_u32::<BigEndian>(last | (block_type as u32) << 24 | block.len() as u32)?;
+            writer.write_all(&block.data)?;
+        }
+
+        writer.write_all(&self.frames)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    pub fn find_block(&self, block_type: FlacBlockType) -> Option<&FlacBlock> {
+        self.blocks.iter().find(|b| b.block_type == block_type)
+    }
+
+    /// The chunks of the original WAV stored by `flac --keep-foreign-metadata`, joined back
+    /// together. The data chunk is only a header, as the audio is in the frames.
+    pub fn foreign_metadata(&self) -> Vec<u8> {
+        self.blocks
+            .iter()
+            .filter(|b| b.application_id() == Some(RIFF_APPLICATION_ID))
+            .flat_map(|b| b.data[4..].iter())
+            .copied()
+            .collect()
+    }
+
+    /// Decode the frames to interleaved samples, along with the stream info describing them.
+    pub fn decode(&self) -> Result<(StreamInfo, Vec<i32>), WavTagError> {
+        let info = self.stream_info()?;
+
+        // the decoder only needs the stream info ahead of the frames.
+        let mut stream = Vec::with_capacity(42 + self.frames.len());
+        stream.extend_from_slice(b"fLaC");
+        stream.push(0x80); // last block, stream info
+        stream.extend_from_slice(&[0, 0, 34]);
+        stream.extend_from_slice(&info.serialise());
+        stream.extend_from_slice(&self.frames);
+
+        let mut reader = claxon::FlacReader::new(Cursor::new(stream)).map_err(decode_error)?;
+        let samples = reader
+            .samples()
+            .collect::<Result<Vec<i32>, claxon::Error>>()
+            .map_err(decode_error)?;
+
+        Ok((info, samples))
+    }
+
+    /// The audio converted to a WAV data chunk, along with the matching format chunk. Samples
+    /// that aren't a whole number of bytes are left justified, as WAV requires.
+    pub fn wave_data(&self) -> Result<(WaveFormat, Vec<u8>), WavTagError> {
+        let (info, samples) = self.decode()?;
+
+        let bits_per_sample = info.bits_per_sample as u16;
+        let container = bits_per_sample.div_ceil(8) * 8;
+        let shift = container - bits_per_sample;
+
+        let samples = match container {
+            8 => Samples::U8(
+                samples
+                    .iter()
+                    .map(|&s| ((s << shift) + 128) as u8)
+                    .collect(),
+            ),
+            16 => Samples::I16(samples.iter().map(|&s| (s << shift) as i16).collect()),
+            24 => Samples::I24(samples.iter().map(|&s| s << shift).collect()),
+            _ => Samples::I32(samples.iter().map(|&s| s << shift).collect()),
+        };
+        let audio = AudioData {
+            channels: info.channels as u16,
+            samples,
+        };
+
+        let mut format = WaveFormat::new(
+            FormatTag::Pcm,
+            info.channels as u16,
+            info.sample_rate,
+            container,
+        );
+        if let Some(ext) = format.extensible.as_mut() {
+            ext.valid_bits_per_sample = bits_per_sample;
+        }
+
+        Ok((format, audio.encode()))
+    }
+}
+
+impl FlacFile {
+    /// Encode a WAV file as FLAC. Every chunk but the audio is kept in `riff` application blocks,
+    /// so the WAV can be restored exactly by `RiffFile::from_flac` or `flac -d
+    /// --keep-foreign-metadata`.
+    pub fn from_riff(riff: &RiffFile) -> Result<Self, WavTagError> {
+        let format = riff.format()?;
+
+        // the kept fmt and fact chunks would describe the compressed audio, not the PCM stream.
+        if format.is_compressed() {
+            return Err(WavTagError::Unsupported(format!(
+                "can't write {:?} to FLAC without transcoding it to PCM",
+                format.sample_format()
+            )));
+        }
+
+        let audio = riff.samples()?;
+
+        let (samples, bits_per_sample): (Vec<i32>, u32) = match &audio.samples {
+            Samples::U8(s) => (s.iter().map(|&v| v as i32 - 128).collect(), 8),
+            Samples::I16(s) => (s.iter().map(|&v| v as i32).collect(), 16),
+            Samples::I24(s) => (s.clone(), 24),
+            other => {
+                let (tag, bits) = other.format();
+                return Err(WavTagError::Unsupported(format!(
+                    "can't write {:?} with {} bit samples to FLAC",
+                    tag, bits
+                )));
+            }
+        };
+
+        if format.channels == 0 || format.channels > 8 {
+            return Err(WavTagError::Unsupported(format!(
+                "can't write {} channels to FLAC",
+                format.channels
+            )));
+        }
+        if format.sample_rate == 0 || format.sample_rate > 0xf_ffff {
+            return Err(WavTagError::Unsupported(format!(
+                "can't write a sample rate of {} to FLAC",
+                format.sample_rate
+            )));
+        }
+        if riff.len() > u32::MAX as usize {
+            return Err(WavTagError::Unsupported(
+                "can't keep the chunks of a wav over 4GB in FLAC".to_string(),
+            ));
+        }
+
+        let channels = format.channels as usize;
+        let samples = &samples[..samples.len() - samples.len() % channels];
+
+        let frames = encode::encode(samples, channels, format.sample_rate, bits_per_sample);
+
+        // the decoder verifies the samples against their MD5, in the sample format of the
+        // stream.
+        let mut md5 = md5::Context::new();
+        let bytes = bits_per_sample as usize / 8;
+        let mut buf = [0u8; 4];
+        for &sample in samples.iter() {
+            LittleEndian::write_i32(&mut buf, sample);
+            md5.consume(&buf[..bytes]);
+        }
+
+        let mut file = FlacFile {
+            filename: riff.filename.clone(),
+            blocks: Vec::new(),
+            frames: frames.data,
+        };
+        file.set_stream_info(StreamInfo {
+            min_block_size: encode::BLOCK_SIZE as u16,
+            max_block_size: encode::BLOCK_SIZE as u16,
+            min_frame_size: frames.min_frame_size,
+            max_frame_size: frames.max_frame_size,
+            sample_rate: format.sample_rate,
+            channels: format.channels as u8,
+            bits_per_sample: bits_per_sample as u8,
+            total_samples: (samples.len() / channels) as u64,
+            md5: md5.compute().0,
+        });
+
+        // the loop is also kept in the comments, for players that don't read the smpl chunk.
+        let mut comments = VorbisComments {
+            vendor: "wavtag".to_string(),
+            ..VorbisComments::default()
+        };
+        if riff.find_chunk_by_type(ChunkType::Sampler).is_some() {
+            comments.set_loops(&riff.get_sampler_chunk()?.sample_loops);
+        }
+        file.set_vorbis_comments(comments);
+
+        // the RIFF header (with the ds64 chunk of an RF64 or BW64 file) and every chunk get a
+        // block each, with only the header of the data chunk.
+        let form = riff.output_form();
+        let mut header = Vec::new();
+        riff.write_header(&mut header, form)?;
+        file.push_foreign_metadata(&header);
+
+        for chunk in riff.chunks.iter() {
+            let mut data = Vec::with_capacity(8 + chunk.len() + 1);
+            data.extend_from_slice(&chunk.tag);
+            data.write_u32::<LittleEndian>(chunk.size_field(form))?;
+
+            if chunk.header != ChunkType::Data {
+                data.extend_from_slice(&riff.read_chunk_data(chunk)?);
+                if !chunk.len().is_multiple_of(2) {
+                    data.push(0); // pad byte
+                }
+            }
+
+            file.push_foreign_metadata(&data);
+        }
+
+        Ok(file)
+    }
+
+    /// Add foreign metadata in `riff` application blocks, split if too large for one block.
+    fn push_foreign_metadata(&mut self, data: &[u8]) {
+        for part in data.chunks(MAX_BLOCK_LEN - 4) {
+            let mut block = RIFF_APPLICATION_ID.to_vec();
+            block.extend_from_slice(part);
+            self.blocks
+                .push(FlacBlock::new(FlacBlockType::Application, block));
+        }
+    }
+}
+
+impl RiffFile {
+    /// Decode a FLAC file to WAV. The chunks kept by `flac --keep-foreign-metadata` are restored
+    /// in their original order, otherwise the format chunk is made from the stream info. Without
+    /// a smpl chunk the loop is read from the `LOOPSTART` comments.
+    pub fn from_flac(flac: &FlacFile) -> Result<Self, WavTagError> {
+        let (format, data) = flac.wave_data()?;
+
+        let mut file = RiffFile {
+            filename: flac.filename.clone(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+
+        let foreign = flac.foreign_metadata();
+        if foreign.is_empty() {
+            file.chunks.push(RiffChunk::new(ChunkType::Data, data));
+        } else {
+            file.read_foreign_metadata(&foreign, data)?;
+        }
+
+        if file.find_chunk_by_type(ChunkType::Format).is_none() {
+            file.set_format(format);
+        }
+
+        if file.find_chunk_by_type(ChunkType::Sampler).is_none() {
+            let frames = file.metadata()?.frames;
+            file.set_metadata(&SampleMetadata {
+                loops: flac.get_vorbis_comments()?.loops(frames),
+                ..SampleMetadata::default()
+            })?;
+        }
+
+        Ok(file)
+    }
+
+    /// Rebuild the chunks from foreign metadata, with `audio` as the body of the data chunk.
+    fn read_foreign_metadata(&mut self, foreign: &[u8], audio: Vec<u8>) -> Result<(), WavTagError> {
+        let malformed = |reason: &str| WavTagError::MalformedChunk {
+            chunk: ChunkType::Unknown("riff".to_string()),
+            offset: None,
+            reason: reason.to_string(),
+        };
+
+        if foreign.len() < 12 || &foreign[8..12] != b"WAVE" {
+            return Err(malformed(
+                "foreign metadata doesn't start with a WAVE header",
+            ));
+        }
+        self.form = match &foreign[..4] {
+            b"RIFF" => RiffForm::Riff,
+            b"RF64" => RiffForm::Rf64,
+            b"BW64" => RiffForm::Bw64,
+            _ => return Err(malformed("foreign metadata isn't from a RIFF file")),
+        };
+
+        let mut audio = Some(audio);
+        let mut offset = 12;
+        while offset < foreign.len() {
+            if foreign.len() - offset < 8 {
+                return Err(malformed(
+                    "foreign metadata ends part way through a chunk header",
+                ));
+            }
+            let tag: [u8; 4] = foreign[offset..offset + 4].try_into().expect("4 bytes");
+            let len = LittleEndian::read_u32(&foreign[offset + 4..offset + 8]) as usize;
+            offset += 8;
+
+            // the data chunk is only a header, its body is the audio.
+            let body = if &tag == b"data" {
+                audio.take().unwrap_or_default()
+            } else {
+                let body = match foreign.get(offset..offset + len) {
+                    Some(body) => body.to_vec(),
+                    None => {
+                        return Err(malformed("foreign metadata ends part way through a chunk"))
+                    }
+                };
+                offset += len + len % 2;
+                body
+            };
+
+            // the ds64 chunk is regenerated when a 64-bit file is written.
+            if &tag != b"ds64" {
+                let mut chunk = RiffChunk::new(header_to_rifftype(tag), body);
+                chunk.tag = tag;
+                self.chunks.push(chunk);
+            }
+        }
+
+        if let Some(audio) = audio {
+            self.chunks.push(RiffChunk::new(ChunkType::Data, audio));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LoopType, SampleLoop, SamplerChunk};
+
+    /// A wav of a noisy ramp, long enough for several FLAC frames and a short last one, with a
+    /// loop so there's a chunk after the data chunk too.
+    fn wav(channels: u16, bits_per_sample: u16, form: RiffForm) -> Vec<u8> {
+        let len = 10007 * channels as usize;
+        let mut seed = 1u32;
+        let noise: Vec<i32> = (0..len)
+            .map(|i| {
+                seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+                (i as i32 * 13) + (seed >> 24) as i32
+            })
+            .collect();
+
+        let samples = match bits_per_sample {
+            8 => Samples::U8(noise.iter().map(|&s| s as u8).collect()),
+            16 => Samples::I16(noise.iter().map(|&s| s.wrapping_mul(97) as i16).collect()),
+            _ => Samples::I24(
+                noise
+                    .iter()
+                    .map(|&s| s.wrapping_mul(24593) << 8 >> 8)
+                    .collect(),
+            ),
+        };
+
+        let mut riff = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form,
+            source: None,
+        };
+        riff.set_format(WaveFormat::new(
+            FormatTag::Pcm,
+            channels,
+            44100,
+            bits_per_sample,
+        ));
+        riff.set_samples(&AudioData { channels, samples }).unwrap();
+        riff.set_sampler_chunk(SamplerChunk {
+            sample_loops: vec![SampleLoop {
+                id: 0,
+                loop_type: LoopType::Forward,
+                start: 100,
+                end: 9999,
+                fraction: 0,
+                play_count: 0,
+            }],
+            ..SamplerChunk::default()
+        });
+
+        let mut bytes = Vec::new();
+        riff.write(&mut bytes).unwrap();
+        bytes
+    }
+
+    fn round_trip(bytes: &[u8]) -> Vec<u8> {
+        let riff = RiffFile::read(Cursor::new(bytes), "test.wav".to_string()).unwrap();
+
+        let mut flac = Vec::new();
+        FlacFile::from_riff(&riff)
+            .unwrap()
+            .write(&mut flac)
+            .unwrap();
+        let flac = FlacFile::read(Cursor::new(flac), "test.flac".to_string()).unwrap();
+
+        let mut out = Vec::new();
+        RiffFile::from_flac(&flac).unwrap().write(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trips_every_bit_depth_and_channel_count() {
+        for bits_per_sample in [8, 16, 24] {
+            for channels in [1, 2] {
+                let bytes = wav(channels, bits_per_sample, RiffForm::Riff);
+                assert!(
+                    round_trip(&bytes) == bytes,
+                    "{} bit, {} channel wav changed",
+                    bits_per_sample,
+                    channels
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn refuses_compressed_wavs() {
+        let mut riff = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        riff.set_format(WaveFormat::new(FormatTag::MuLaw, 1, 8000, 8));
+        riff.chunks
+            .push(RiffChunk::new(ChunkType::Data, vec![0xff; 100]));
+
+        assert!(matches!(
+            FlacFile::from_riff(&riff),
+            Err(WavTagError::Unsupported(_))
+        ));
+
+        riff.transcode_to_pcm().unwrap();
+        assert!(FlacFile::from_riff(&riff).is_ok());
+    }
+
+    #[test]
+    fn round_trips_rf64() {
+        let bytes = wav(2, 16, RiffForm::Rf64);
+        assert_eq!(&bytes[..4], b"RF64");
+        assert!(round_trip(&bytes) == bytes);
+    }
+}
@@ -0,0 +1,109 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::flac::{FlacBlock, FlacBlockType, FlacFile};
+use crate::{ChunkType, WavTagError};
+
+/// The stream info block describes the audio, like the WAV format chunk. It is always the first
+/// metadata block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StreamInfo {
+    /// The smallest and largest number of samples per channel in a frame. The last frame may be
+    /// smaller than `min_block_size`.
+    pub min_block_size: u16,
+    pub max_block_size: u16,
+
+    /// The smallest and largest frame in bytes, or 0 if unknown.
+    pub min_frame_size: u32,
+    pub max_frame_size: u32,
+
+    pub sample_rate: u32,
+    pub channels: u8,
+    pub bits_per_sample: u8,
+
+    /// The number of sample frames in the stream, or 0 if unknown.
+    pub total_samples: u64,
+
+    /// The MD5 of the decoded samples, or all zeros if unknown.
+    pub md5: [u8; 16],
+}
+
+impl StreamInfo {
+    pub fn from_block(block: &FlacBlock) -> Result<Self, WavTagError> {
+        block.check_type(FlacBlockType::StreamInfo)?;
+
+        Self::read_body(&block.data).map_err(|e| block.body_error(e))
+    }
+
+    fn read_body(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+
+        let min_block_size = data.read_u16::<BigEndian>()?;
+        let max_block_size = data.read_u16::<BigEndian>()?;
+        let min_frame_size = data.read_u24::<BigEndian>()?;
+        let max_frame_size = data.read_u24::<BigEndian>()?;
+
+        // 20 bits of sample rate, 3 of channels - 1, 5 of bits per sample - 1 and 36 of samples.
+        let packed = data.read_u64::<BigEndian>()?;
+
+        let mut md5 = [0u8; 16];
+        data.read_exact(&mut md5)?;
+
+        Ok(StreamInfo {
+            min_block_size,
+            max_block_size,
+            min_frame_size,
+            max_frame_size,
+            sample_rate: (packed >> 44) as u32,
+            channels: ((packed >> 41) & 0x7) as u8 + 1,
+            bits_per_sample: ((packed >> 36) & 0x1f) as u8 + 1,
+            total_samples: packed & 0xf_ffff_ffff,
+            md5,
+        })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut block = Vec::with_capacity(34);
+
+        let packed = (self.sample_rate as u64 & 0xf_ffff) << 44
+            | (self.channels.saturating_sub(1) as u64 & 0x7) << 41
+            | (self.bits_per_sample.saturating_sub(1) as u64 & 0x1f) << 36
+            | self.total_samples & 0xf_ffff_ffff;
+
+        // writes to a vec are infallible.
+        block.write_u16::<BigEndian>(self.min_block_size).unwrap();
+        block.write_u16::<BigEndian>(self.max_block_size).unwrap();
+        block.write_u24::<BigEndian>(self.min_frame_size).unwrap();
+        block.write_u24::<BigEndian>(self.max_frame_size).unwrap();
+        block.write_u64::<BigEndian>(packed).unwrap();
+        block.extend_from_slice(&self.md5);
+
+        block
+    }
+}
+
+impl FlacFile {
+    pub fn stream_info(&self) -> Result<StreamInfo, WavTagError> {
+        match self.find_block(FlacBlockType::StreamInfo) {
+            Some(b) => StreamInfo::from_block(b),
+            None => Err(WavTagError::MissingChunk(ChunkType::Unknown(
+                "STREAMINFO".to_string(),
+            ))),
+        }
+    }
+
+    /// Replace the stream info, which must be the first block.
+    pub fn set_stream_info(&mut self, info: StreamInfo) {
+        let block = FlacBlock::new(FlacBlockType::StreamInfo, info.serialise());
+        match self
+            .blocks
+            .iter()
+            .position(|b| b.block_type == FlacBlockType::StreamInfo)
+        {
+            Some(index) => self.blocks[index] = block,
+            None => self.blocks.insert(0, block),
+        }
+    }
+}
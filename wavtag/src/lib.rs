@@ -1,12 +1,35 @@
 extern crate byteorder;
+pub use xmltree;
+
+mod error;
+pub use error::*;
 
 mod riff;
 pub use riff::*;
 
 pub mod utils;
 
+mod repair;
+pub use repair::*;
+mod validate;
+pub use validate::*;
+
 mod chunks;
 pub use chunks::*;
 
+mod aiff;
+pub use aiff::*;
+
+mod flac;
+pub use flac::*;
+
+mod vorbis;
+pub use vorbis::*;
+
+mod ogg;
+pub use crate::ogg::*;
+
+mod metadata;
+pub use metadata::*;
+
 mod midi;
-pub use midi::*;
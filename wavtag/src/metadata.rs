@@ -0,0 +1,436 @@
+use crate::{
+    AiffComment, AiffFile, AiffInstrumentChunk, AiffLoop, AiffMarker, ChunkType, CommentsChunk,
+    CuePoint, InstrumentChunk, LoopType, MarkerChunk, OggFile, PlayMode, RiffFile, SampleLoop,
+    WavTagError,
+};
+
+/// The sampler metadata that WAV, AIFF and Ogg files can hold, so each format can be read the
+/// same way and converted to the others.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SampleMetadata {
+    pub channels: u16,
+    pub sample_rate: u32,
+    pub bits_per_sample: u16,
+    pub frames: u64,
+
+    /// The unity note, tuning, gain and key and velocity ranges, if the file has any.
+    pub instrument: Option<InstrumentChunk>,
+
+    pub markers: Vec<Marker>,
+
+    /// Loops in sample frames. As in the smpl chunk the end is the last frame played, and the id
+    /// is the id of the marker at the start of the loop.
+    pub loops: Vec<SampleLoop>,
+
+    pub comments: Vec<String>,
+}
+
+/// A named position in the audio, a cue point in WAV or a marker in AIFF.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Marker {
+    pub id: u32,
+    pub position: u32,
+    pub name: String,
+}
+
+impl RiffFile {
+    pub fn metadata(&self) -> Result<SampleMetadata, WavTagError> {
+        let format = self.format()?;
+        let frames = self
+            .find_chunk_by_type(ChunkType::Data)
+            .map_or(0, |c| format.frame_count(c.len()));
+
+        let smpl = match self.find_chunk_by_type(ChunkType::Sampler) {
+            Some(_) => Some(self.get_sampler_chunk()?),
+            None => None,
+        };
+
+        // without an inst chunk the unity note can still come from the smpl chunk.
+        let instrument = match (self.find_chunk_by_type(ChunkType::Instrument), &smpl) {
+            (Some(_), _) => Some(self.get_instrument_chunk()?),
+            (None, Some(smpl)) => Some(InstrumentChunk {
+                unshifted_note: smpl.midi_unity_note.min(127) as u8,
+                ..InstrumentChunk::default()
+            }),
+            (None, None) => None,
+        };
+
+        let adtl = self.get_associated_data_chunk()?;
+        let markers = self
+            .get_cue_chunk()?
+            .cue_points
+            .iter()
+            .map(|c| Marker {
+                id: c.id,
+                position: c.position,
+                name: adtl.label(c.id).unwrap_or_default().to_string(),
+            })
+            .collect();
+
+        Ok(SampleMetadata {
+            channels: format.channels,
+            sample_rate: format.sample_rate,
+            bits_per_sample: format.bits_per_sample,
+            frames: frames as u64,
+            instrument,
+            markers,
+            loops: smpl.map_or_else(Vec::new, |s| s.sample_loops),
+            comments: self
+                .get_info_chunk()?
+                .comment()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        })
+    }
+
+    /// Write the instrument, markers, loops and comments into the inst, cue, adtl, smpl and INFO
+    /// chunks. The format fields are left alone, as they describe the data chunk.
+    pub fn set_metadata(&mut self, metadata: &SampleMetadata) -> Result<(), WavTagError> {
+        if let Some(inst) = metadata.instrument {
+            self.set_instrument_chunk(inst);
+        }
+
+        if !metadata.markers.is_empty() {
+            let mut cue = self.get_cue_chunk()?;
+            let mut adtl = self.get_associated_data_chunk()?;
+
+            for marker in metadata.markers.iter() {
+                cue.cue_points.retain(|c| c.id != marker.id);
+                cue.cue_points.push(CuePoint {
+                    id: marker.id,
+                    position: marker.position,
+                    sample_offset: marker.position,
+                    ..CuePoint::default()
+                });
+
+                if !marker.name.is_empty() {
+                    adtl.set_label(marker.id, &marker.name);
+                }
+            }
+
+            self.set_cue_chunk(cue);
+            if !adtl.entries.is_empty() {
+                self.set_associated_data_chunk(adtl);
+            }
+        }
+
+        if !metadata.loops.is_empty() {
+            let inst = metadata.instrument.unwrap_or_default();
+            let mut smpl = self.get_sampler_chunk()?;
+
+            smpl.sample_period = self.format().map_or(0, |f| f.sample_period());
+            smpl.midi_unity_note = inst.unshifted_note as u32;
+            // the pitch fraction can only tune upwards.
+            smpl.midi_pitch_fraction = if inst.fine_tune > 0 {
+                ((inst.fine_tune as u64) << 32).div_ceil(100) as u32
+            } else {
+                0
+            };
+            smpl.sample_loops = metadata.loops.clone();
+
+            self.set_sampler_chunk(smpl);
+        }
+
+        if !metadata.comments.is_empty() {
+            let mut info = self.get_info_chunk()?;
+            info.set_comment(&metadata.comments.join("\n"));
+            self.set_info_chunk(info);
+        }
+
+        Ok(())
+    }
+}
+
+/// A loop of the AIFF instrument chunk as a smpl loop, if it loops and its markers exist.
+fn aiff_loop(aiff_loop: &AiffLoop, markers: &MarkerChunk) -> Option<SampleLoop> {
+    let loop_type = match aiff_loop.play_mode {
+        PlayMode::NoLooping => return None,
+        PlayMode::Forward => LoopType::Forward,
+        PlayMode::ForwardBackward => LoopType::PingPong,
+        PlayMode::Other(other) => LoopType::Other(other as u32),
+    };
+
+    let begin = markers.find_marker(aiff_loop.begin_loop)?;
+    let end = markers.find_marker(aiff_loop.end_loop)?;
+
+    Some(SampleLoop {
+        id: begin.id as u32,
+        loop_type,
+        start: begin.position,
+        // the end marker is after the last frame played.
+        end: end.position.saturating_sub(1),
+        fraction: 0,
+        play_count: 0,
+    })
+}
+
+impl AiffFile {
+    pub fn metadata(&self) -> Result<SampleMetadata, WavTagError> {
+        let common = self.common()?;
+        let markers = self.get_marker_chunk()?;
+
+        let (instrument, loops) = match self.find_chunk(*b"INST") {
+            Some(_) => {
+                let inst = self.get_instrument_chunk()?;
+                let instrument = InstrumentChunk {
+                    unshifted_note: inst.base_note,
+                    fine_tune: inst.detune,
+                    gain: inst.gain.clamp(-64, 64) as i8,
+                    low_note: inst.low_note,
+                    high_note: inst.high_note,
+                    low_vel: inst.low_velocity,
+                    high_vel: inst.high_velocity,
+                };
+                let loops = [inst.sustain_loop, inst.release_loop]
+                    .iter()
+                    .filter_map(|l| aiff_loop(l, &markers))
+                    .collect();
+                (Some(instrument), loops)
+            }
+            None => (None, Vec::new()),
+        };
+
+        Ok(SampleMetadata {
+            channels: common.channels,
+            sample_rate: common.sample_rate.round() as u32,
+            bits_per_sample: common.sample_size,
+            frames: common.sample_frames as u64,
+            instrument,
+            markers: markers
+                .markers
+                .into_iter()
+                .map(|m| Marker {
+                    id: m.id as u32,
+                    position: m.position,
+                    name: m.name,
+                })
+                .collect(),
+            loops,
+            comments: self
+                .get_comments_chunk()?
+                .comments
+                .into_iter()
+                .map(|c| c.text)
+                .collect(),
+        })
+    }
+
+    /// Write the instrument, markers, loops and comments into the INST, MARK and COMT chunks.
+    /// The first two loops become the sustain and release loops, with markers added at their
+    /// ends where there isn't one already.
+    pub fn set_metadata(&mut self, metadata: &SampleMetadata) {
+        // AIFF marker ids are 16-bit and start at 1, so the markers are renumbered.
+        let mut markers: Vec<AiffMarker> = metadata
+            .markers
+            .iter()
+            .enumerate()
+            .map(|(i, m)| AiffMarker {
+                id: i as u16 + 1,
+                position: m.position,
+                name: m.name.clone(),
+            })
+            .collect();
+
+        let mut marker_at = |position: u32, name: String| -> u16 {
+            match markers.iter().find(|m| m.position == position) {
+                Some(marker) => marker.id,
+                None => {
+                    let id = markers.len() as u16 + 1;
+                    markers.push(AiffMarker { id, position, name });
+                    id
+                }
+            }
+        };
+
+        let mut loops = Vec::new();
+        for (i, sample_loop) in metadata.loops.iter().take(2).enumerate() {
+            loops.push(AiffLoop {
+                // AIFF has no reverse loops.
+                play_mode: match sample_loop.loop_type {
+                    LoopType::Forward | LoopType::Reverse => PlayMode::Forward,
+                    LoopType::PingPong => PlayMode::ForwardBackward,
+                    LoopType::Other(other) => PlayMode::Other(other as u16),
+                },
+                begin_loop: marker_at(sample_loop.start, format!("Loop {} start", i + 1)),
+                // the end marker goes after the last frame played.
+                end_loop: marker_at(
+                    sample_loop.end.saturating_add(1),
+                    format!("Loop {} end", i + 1),
+                ),
+            });
+        }
+
+        if metadata.instrument.is_some() || !loops.is_empty() {
+            let inst = metadata.instrument.unwrap_or_default();
+            self.set_instrument_chunk(AiffInstrumentChunk {
+                base_note: inst.unshifted_note,
+                detune: inst.fine_tune,
+                low_note: inst.low_note,
+                high_note: inst.high_note,
+                low_velocity: inst.low_vel,
+                high_velocity: inst.high_vel,
+                gain: inst.gain as i16,
+                sustain_loop: loops.first().copied().unwrap_or_default(),
+                release_loop: loops.get(1).copied().unwrap_or_default(),
+            });
+        }
+
+        if !markers.is_empty() {
+            self.set_marker_chunk(MarkerChunk { markers });
+        }
+
+        if !metadata.comments.is_empty() {
+            self.set_comments_chunk(CommentsChunk {
+                comments: metadata
+                    .comments
+                    .iter()
+                    .map(|text| AiffComment {
+                        text: text.clone(),
+                        ..AiffComment::default()
+                    })
+                    .collect(),
+            });
+        }
+    }
+}
+
+impl OggFile {
+    /// Ogg files can only hold a single loop, in the `LOOPSTART` comments, and text comments.
+    pub fn metadata(&self) -> Result<SampleMetadata, WavTagError> {
+        let comments = self.get_vorbis_comments()?;
+        let frames = self.frames();
+
+        Ok(SampleMetadata {
+            channels: self.channels(),
+            sample_rate: self.sample_rate(),
+            // the decoder's output, as the compressed audio has no bit depth.
+            bits_per_sample: 16,
+            frames,
+            instrument: None,
+            markers: Vec::new(),
+            loops: comments.loops(frames),
+            comments: comments.get_all("COMMENT").map(String::from).collect(),
+        })
+    }
+
+    /// Write the first loop and the comments to the Vorbis comments. The audio is left alone.
+    pub fn set_metadata(&mut self, metadata: &SampleMetadata) -> Result<(), WavTagError> {
+        let mut comments = self.get_vorbis_comments()?;
+
+        if !metadata.loops.is_empty() {
+            comments.set_loops(&metadata.loops);
+        }
+
+        if !metadata.comments.is_empty() {
+            comments.set("COMMENT", &metadata.comments.join("\n"));
+        }
+
+        self.set_vorbis_comments(comments);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FormatTag, RiffChunk, RiffForm, WaveFormat};
+    use std::io::Cursor;
+
+    /// A mono 16-bit wav of 200 frames with an instrument, a marker and a loop from it.
+    fn sample_wav() -> RiffFile {
+        let mut file = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(FormatTag::Pcm, 1, 44100, 16));
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, vec![0; 400]));
+        file.set_metadata(&SampleMetadata {
+            instrument: Some(InstrumentChunk {
+                unshifted_note: 48,
+                fine_tune: -12,
+                gain: -6,
+                low_note: 36,
+                high_note: 60,
+                low_vel: 10,
+                high_vel: 100,
+            }),
+            markers: vec![Marker {
+                id: 1,
+                position: 10,
+                name: "Loop".to_string(),
+            }],
+            loops: vec![SampleLoop {
+                id: 1,
+                loop_type: LoopType::Forward,
+                start: 10,
+                end: 99,
+                fraction: 0,
+                play_count: 0,
+            }],
+            ..SampleMetadata::default()
+        })
+        .unwrap();
+        file
+    }
+
+    #[test]
+    fn maps_loops_to_markers_and_the_sustain_loop() {
+        let aiff = AiffFile::from_riff(&sample_wav()).unwrap();
+
+        // the loop starts at the existing marker, and a marker is added after its last frame.
+        let markers = aiff.get_marker_chunk().unwrap().markers;
+        assert_eq!(
+            markers,
+            [
+                AiffMarker {
+                    id: 1,
+                    position: 10,
+                    name: "Loop".to_string(),
+                },
+                AiffMarker {
+                    id: 2,
+                    position: 100,
+                    name: "Loop 1 end".to_string(),
+                },
+            ]
+        );
+
+        let inst = aiff.get_instrument_chunk().unwrap();
+        assert_eq!(
+            inst.sustain_loop,
+            AiffLoop {
+                play_mode: PlayMode::Forward,
+                begin_loop: 1,
+                end_loop: 2,
+            }
+        );
+        assert_eq!(inst.release_loop.play_mode, PlayMode::NoLooping);
+    }
+
+    #[test]
+    fn round_trips_the_instrument_through_wav() {
+        let wav = sample_wav();
+        let aiff = AiffFile::from_riff(&wav).unwrap();
+        let inst = aiff.get_instrument_chunk().unwrap();
+        assert_eq!((inst.base_note, inst.detune, inst.gain), (48, -12, -6));
+        assert_eq!((inst.low_note, inst.high_note), (36, 60));
+        assert_eq!((inst.low_velocity, inst.high_velocity), (10, 100));
+
+        let mut bytes = Vec::new();
+        aiff.write(&mut bytes).unwrap();
+        let aiff = AiffFile::read(Cursor::new(bytes), "test.aif".to_string()).unwrap();
+
+        let back = RiffFile::from_aiff(&aiff).unwrap();
+        let metadata = back.metadata().unwrap();
+        assert_eq!(metadata.instrument, wav.metadata().unwrap().instrument);
+        assert_eq!(metadata.loops, wav.metadata().unwrap().loops);
+
+        let aiff = AiffFile::from_riff(&back).unwrap();
+        assert_eq!(aiff.get_instrument_chunk().unwrap(), inst);
+    }
+}
@@ -0,0 +1,251 @@
+use byteorder::{ByteOrder, LittleEndian};
+
+use std::io::{Cursor, Read, Seek, Write};
+
+use ::ogg::reading::{OggReadError, PacketReader};
+use ::ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use crate::{
+    AudioData, ChunkType, FormatTag, RiffChunk, RiffFile, RiffForm, Samples, VorbisComments,
+    WavTagError, WaveFormat,
+};
+
+/// The codecs an Ogg file can be read with. Both keep their tags in Vorbis comments in the
+/// second header packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OggCodec {
+    Vorbis,
+    Opus,
+}
+
+impl OggCodec {
+    /// The bytes before the comments in the comment header packet.
+    fn comment_magic(self) -> &'static [u8] {
+        match self {
+            OggCodec::Vorbis => b"\x03vorbis",
+            OggCodec::Opus => b"OpusTags",
+        }
+    }
+}
+
+/// A packet of an Ogg stream, along with where it sat in its page so the stream can be written
+/// back with the same pages.
+pub struct OggPacket {
+    pub data: Vec<u8>,
+
+    /// The granule position of the page the packet ends on: the number of sample frames decoded
+    /// by the end of that page.
+    pub granule_position: u64,
+
+    pub last_in_page: bool,
+}
+
+/// The single logical stream of an Ogg Vorbis or Opus file. The audio is kept as it is, only the
+/// comments can be changed.
+pub struct OggFile {
+    pub filename: String,
+    pub codec: OggCodec,
+    pub serial: u32,
+    pub packets: Vec<OggPacket>,
+}
+
+fn ogg_error(e: OggReadError) -> WavTagError {
+    match e {
+        OggReadError::ReadError(e) => WavTagError::Io(e),
+        e => WavTagError::Decode(e.to_string()),
+    }
+}
+
+impl OggFile {
+    pub fn read<R: Read + Seek>(reader: R, filename: String) -> Result<Self, WavTagError> {
+        let mut reader = PacketReader::new(reader);
+
+        let first = match reader.read_packet().map_err(ogg_error)? {
+            Some(packet) => packet,
+            None => {
+                return Err(WavTagError::Decode(
+                    "the ogg file has no packets".to_string(),
+                ))
+            }
+        };
+
+        let codec = if first.data.starts_with(b"\x01vorbis") {
+            OggCodec::Vorbis
+        } else if first.data.starts_with(b"OpusHead") {
+            OggCodec::Opus
+        } else {
+            return Err(WavTagError::Unsupported(
+                "the ogg file isn't Vorbis or Opus".to_string(),
+            ));
+        };
+
+        let serial = first.stream_serial();
+        let mut packets = Vec::new();
+        let mut packet = Some(first);
+
+        while let Some(p) = packet {
+            if p.stream_serial() != serial {
+                return Err(WavTagError::Unsupported(
+                    "ogg files with more than one stream aren't supported".to_string(),
+                ));
+            }
+
+            packets.push(OggPacket {
+                granule_position: p.absgp_page(),
+                last_in_page: p.last_in_page(),
+                data: p.data,
+            });
+            packet = reader.read_packet().map_err(ogg_error)?;
+        }
+
+        if packets.len() < 2 {
+            return Err(WavTagError::Decode(
+                "the ogg file has no comment header".to_string(),
+            ));
+        }
+
+        Ok(OggFile {
+            filename,
+            codec,
+            serial,
+            packets,
+        })
+    }
+
+    pub fn write<W: Write>(&self, writer: W) -> Result<(), WavTagError> {
+        let mut writer = PacketWriter::new(writer);
+
+        for (index, packet) in self.packets.iter().enumerate() {
+            let end = if index + 1 == self.packets.len() {
+                PacketWriteEndInfo::EndStream
+            } else if packet.last_in_page {
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+
+            writer.write_packet(
+                packet.data.clone().into_boxed_slice(),
+                self.serial,
+                end,
+                packet.granule_position,
+            )?;
+        }
+
+        writer.into_inner().flush()?;
+
+        Ok(())
+    }
+
+    pub fn channels(&self) -> u16 {
+        let header = &self.packets[0].data;
+        let offset = match self.codec {
+            OggCodec::Vorbis => 11,
+            OggCodec::Opus => 9,
+        };
+        header.get(offset).copied().unwrap_or(0) as u16
+    }
+
+    /// The rate the audio decodes at. Opus always decodes at 48kHz, whatever the rate of the
+    /// original audio.
+    pub fn sample_rate(&self) -> u32 {
+        match self.codec {
+            OggCodec::Vorbis => self.packets[0]
+                .data
+                .get(12..16)
+                .map_or(0, LittleEndian::read_u32),
+            OggCodec::Opus => 48000,
+        }
+    }
+
+    /// The number of sample frames, from the granule position of the last page. Opus streams
+    /// start with `pre-skip` frames that aren't played.
+    pub fn frames(&self) -> u64 {
+        let last = self.packets.last().map_or(0, |p| p.granule_position);
+        match self.codec {
+            OggCodec::Vorbis => last,
+            OggCodec::Opus => {
+                let pre_skip = self.packets[0]
+                    .data
+                    .get(10..12)
+                    .map_or(0, LittleEndian::read_u16);
+                last.saturating_sub(pre_skip as u64)
+            }
+        }
+    }
+
+    pub fn get_vorbis_comments(&self) -> Result<VorbisComments, WavTagError> {
+        let magic = self.codec.comment_magic();
+        let malformed = |reason: String| WavTagError::MalformedChunk {
+            chunk: ChunkType::Unknown("comment header".to_string()),
+            offset: None,
+            reason,
+        };
+
+        match self.packets[1].data.strip_prefix(magic) {
+            Some(body) => VorbisComments::read(body).map_err(|e| malformed(e.to_string())),
+            None => Err(malformed(format!(
+                "expected {:?}",
+                String::from_utf8_lossy(magic)
+            ))),
+        }
+    }
+
+    pub fn set_vorbis_comments(&mut self, comments: VorbisComments) {
+        let mut data = self.codec.comment_magic().to_vec();
+        data.extend_from_slice(&comments.serialise());
+        if self.codec == OggCodec::Vorbis {
+            data.push(1); // framing bit
+        }
+        self.packets[1].data = data;
+    }
+}
+
+impl RiffFile {
+    /// Decode an Ogg Vorbis file to 16-bit WAV, carrying its loop and comments across through
+    /// `SampleMetadata`. Opus audio can't be decoded.
+    pub fn from_ogg(ogg: &OggFile) -> Result<Self, WavTagError> {
+        if ogg.codec != OggCodec::Vorbis {
+            return Err(WavTagError::Unsupported(format!(
+                "can't decode {:?} audio",
+                ogg.codec
+            )));
+        }
+
+        let mut stream = Vec::new();
+        ogg.write(&mut stream)?;
+
+        let mut reader = lewton::inside_ogg::OggStreamReader::new(Cursor::new(stream))
+            .map_err(|e| WavTagError::Decode(e.to_string()))?;
+        let mut samples = Vec::new();
+        while let Some(packet) = reader
+            .read_dec_packet_itl()
+            .map_err(|e| WavTagError::Decode(e.to_string()))?
+        {
+            samples.extend_from_slice(&packet);
+        }
+
+        let audio = AudioData {
+            channels: ogg.channels(),
+            samples: Samples::I16(samples),
+        };
+
+        let mut file = RiffFile {
+            filename: ogg.filename.clone(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(
+            FormatTag::Pcm,
+            ogg.channels(),
+            ogg.sample_rate(),
+            16,
+        ));
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, audio.encode()));
+        file.set_metadata(&ogg.metadata()?)?;
+
+        Ok(file)
+    }
+}
@@ -0,0 +1,408 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+
+use std::fmt;
+use std::io;
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::error::tag_name;
+use crate::riff::{header_to_rifftype, DataSize64};
+use crate::{ChunkType, RiffChunk, RiffFile, RiffForm, WavTagError};
+
+/// A problem fixed by `RiffFile::repair`. The fixes take effect when the file is written.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Repair {
+    /// The RIFF size field doesn't match the length of the file.
+    RiffSize { found: u64, actual: u64 },
+
+    /// A chunk id in the wrong case (eg. `FMT ` or `list`), which is written in the right case.
+    ChunkIdCase {
+        offset: u64,
+        found: [u8; 4],
+        fixed: [u8; 4],
+    },
+
+    /// An odd length chunk that isn't followed by a pad byte.
+    MissingPadByte { offset: u64, tag: [u8; 4] },
+
+    /// A chunk that runs past the end of the file, which is dropped.
+    TruncatedChunk {
+        offset: u64,
+        tag: [u8; 4],
+        size: u64,
+        available: u64,
+    },
+
+    /// A data chunk whose size is larger than the file, which is cut down to the audio present.
+    DataSize {
+        offset: u64,
+        size: u64,
+        available: u64,
+    },
+
+    /// Bytes that aren't a chunk (eg. garbage after the data chunk), which are dropped.
+    Garbage { offset: u64, len: u64 },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Repair::RiffSize { found, actual } => write!(
+                f,
+                "RIFF size was {} bytes, but the file holds {} bytes",
+                found, actual
+            ),
+            Repair::ChunkIdCase {
+                offset,
+                found,
+                fixed,
+            } => write!(
+                f,
+                "renamed {:?} chunk at offset {} to {:?}",
+                tag_name(found),
+                offset,
+                tag_name(fixed)
+            ),
+            Repair::MissingPadByte { offset, tag } => write!(
+                f,
+                "added the missing pad byte to the {:?} chunk at offset {}",
+                tag_name(tag),
+                offset
+            ),
+            Repair::TruncatedChunk {
+                offset,
+                tag,
+                size,
+                available,
+            } => write!(
+                f,
+                "dropped the {:?} chunk at offset {}, which is {} bytes but only {} bytes remain",
+                tag_name(tag),
+                offset,
+                size,
+                available
+            ),
+            Repair::DataSize {
+                offset,
+                size,
+                available,
+            } => write!(
+                f,
+                "cut the \"data\" chunk at offset {} from {} bytes to the {} bytes in the file",
+                offset, size, available
+            ),
+            Repair::Garbage { offset, len } => {
+                write!(f, "dropped {} bytes of garbage at offset {}", len, offset)
+            }
+        }
+    }
+}
+
+/// Whether four bytes could be a chunk id: printable ASCII, not starting with a space.
+fn is_chunk_id(tag: &[u8; 4]) -> bool {
+    tag[0] != b' ' && tag.iter().all(|b| (0x20..0x7f).contains(b))
+}
+
+/// The four bytes at an offset, or `None` if the file ends first.
+fn peek_tag<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<Option<[u8; 4]>, WavTagError> {
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut tag = [0u8; 4];
+    match reader.read_exact(&mut tag) {
+        Ok(()) => Ok(Some(tag)),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+impl RiffFile {
+    /// Read a file that breaks the spec, fixing what can be fixed, and return it along with the
+    /// repairs made. Unlike `read`, only a missing `RIFF`/`WAVE` header (or `ds64` chunk) is an
+    /// error.
+    pub fn repair<R: Read + Seek>(
+        mut reader: R,
+        filename: String,
+    ) -> Result<(Self, Vec<Repair>), WavTagError> {
+        let stream_len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+
+        let mut repairs = Vec::new();
+
+        let mut tag = [0u8; 4];
+        reader.read_exact(&mut tag)?;
+        let form = match &tag {
+            b"RIFF" => RiffForm::Riff,
+            b"RF64" => RiffForm::Rf64,
+            b"BW64" => RiffForm::Bw64,
+            _ => {
+                return Err(WavTagError::BadMagic {
+                    offset: 0,
+                    expected: "RIFF, RF64 or BW64",
+                    found: tag,
+                })
+            }
+        };
+
+        let riff_size = reader.read_u32::<LittleEndian>()?;
+
+        reader.read_exact(&mut tag)?;
+        if &tag != b"WAVE" {
+            return Err(WavTagError::BadMagic {
+                offset: 8,
+                expected: "WAVE",
+                found: tag,
+            });
+        }
+
+        let ds64 = if form != RiffForm::Riff {
+            reader.read_exact(&mut tag)?;
+            if &tag != b"ds64" {
+                return Err(WavTagError::BadMagic {
+                    offset: 12,
+                    expected: "ds64",
+                    found: tag,
+                });
+            }
+
+            let ds64_len = reader.read_u32::<LittleEndian>()?;
+            let ds64_data = crate::utils::read_bytes(
+                &mut reader,
+                crate::utils::padded_size(ds64_len) as usize,
+            )?;
+            let ds64 =
+                DataSize64::from_bytes(&ds64_data).map_err(|e| WavTagError::MalformedChunk {
+                    chunk: ChunkType::Unknown("ds64".to_string()),
+                    offset: Some(12),
+                    reason: e.to_string(),
+                })?;
+            Some(ds64)
+        } else {
+            None
+        };
+
+        let riff_size = ds64.as_ref().map_or(riff_size as u64, |d| d.riff_size);
+        if riff_size != stream_len - 8 {
+            repairs.push(Repair::RiffSize {
+                found: riff_size,
+                actual: stream_len - 8,
+            });
+        }
+
+        let mut chunks = Vec::new();
+        let mut offset = reader.stream_position()?;
+
+        while offset < stream_len {
+            let remaining = stream_len - offset;
+            let tag = match peek_tag(&mut reader, offset)? {
+                Some(tag) if remaining >= 8 && is_chunk_id(&tag) => tag,
+                _ => {
+                    repairs.push(Repair::Garbage {
+                        offset,
+                        len: remaining,
+                    });
+                    break;
+                }
+            };
+
+            let size = reader.read_u32::<LittleEndian>()?;
+            let size = match &ds64 {
+                Some(ds64) if size == u32::MAX => ds64.chunk_size(tag).unwrap_or(size as u64),
+                _ => size as u64,
+            };
+
+            let header = header_to_rifftype(tag);
+
+            let available = remaining - 8;
+            let len = if size <= available {
+                size
+            } else if header == ChunkType::Data {
+                repairs.push(Repair::DataSize {
+                    offset,
+                    size,
+                    available,
+                });
+                available
+            } else {
+                repairs.push(Repair::TruncatedChunk {
+                    offset,
+                    tag,
+                    size,
+                    available,
+                });
+                break;
+            };
+
+            let fixed = header.clone().to_tag();
+            if !matches!(header, ChunkType::Unknown(_)) && fixed != tag {
+                repairs.push(Repair::ChunkIdCase {
+                    offset,
+                    found: tag,
+                    fixed,
+                });
+            }
+
+            let mut chunk =
+                RiffChunk::new(header, crate::utils::read_bytes(&mut reader, len as usize)?);
+            chunk.offset = Some(offset);
+            chunks.push(chunk);
+
+            let chunk_offset = offset;
+            offset += 8 + len;
+
+            // some writers leave out the pad byte, so the next chunk starts right after the body.
+            if len % 2 != 0 && len == size {
+                let padded = peek_tag(&mut reader, offset + 1)?.is_some_and(|t| is_chunk_id(&t));
+                let unpadded = peek_tag(&mut reader, offset)?.is_some_and(|t| is_chunk_id(&t));
+
+                if offset == stream_len || (unpadded && !padded) {
+                    repairs.push(Repair::MissingPadByte {
+                        offset: chunk_offset,
+                        tag,
+                    });
+                } else {
+                    offset += 1;
+                }
+            }
+        }
+
+        let file = RiffFile {
+            filename,
+            chunks,
+            form,
+            source: None,
+        };
+
+        Ok((file, repairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::WriteBytesExt;
+    use std::io::Cursor;
+
+    /// A chunk with the given size field, followed by a pad byte if `pad` is set.
+    fn chunk(tag: &[u8; 4], size: u32, body: &[u8], pad: bool) -> Vec<u8> {
+        let mut bytes = tag.to_vec();
+        bytes.write_u32::<LittleEndian>(size).unwrap();
+        bytes.extend_from_slice(body);
+        if pad {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn fmt_chunk(tag: &[u8; 4]) -> Vec<u8> {
+        let body = [1, 0, 1, 0, 0x44, 0xac, 0, 0, 0x88, 0x58, 1, 0, 2, 0, 16, 0];
+        chunk(tag, 16, &body, false)
+    }
+
+    fn data_chunk() -> Vec<u8> {
+        chunk(b"data", 4, &[1, 0, 2, 0], false)
+    }
+
+    /// A wav of the given chunks with a correct RIFF size.
+    fn wav(chunks: &[Vec<u8>]) -> Vec<u8> {
+        let body = chunks.concat();
+        let mut bytes = b"RIFF".to_vec();
+        bytes
+            .write_u32::<LittleEndian>(body.len() as u32 + 4)
+            .unwrap();
+        bytes.extend_from_slice(b"WAVE");
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    fn repair(bytes: Vec<u8>) -> (RiffFile, Vec<Repair>) {
+        RiffFile::repair(Cursor::new(bytes), "test.wav".to_string()).unwrap()
+    }
+
+    #[test]
+    fn leaves_a_good_file_alone() {
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"fmt "), data_chunk()]));
+        assert_eq!(repairs, []);
+        assert_eq!(file.chunks.len(), 2);
+    }
+
+    #[test]
+    fn repairs_the_riff_size() {
+        let mut bytes = wav(&[fmt_chunk(b"fmt "), data_chunk()]);
+        bytes[4..8].copy_from_slice(&[0; 4]);
+
+        let (_, repairs) = repair(bytes);
+        assert_eq!(
+            repairs,
+            [Repair::RiffSize {
+                found: 0,
+                actual: 40
+            }]
+        );
+    }
+
+    #[test]
+    fn repairs_the_case_of_chunk_ids() {
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"FMT "), data_chunk()]));
+        assert_eq!(
+            repairs,
+            [Repair::ChunkIdCase {
+                offset: 12,
+                found: *b"FMT ",
+                fixed: *b"fmt "
+            }]
+        );
+        assert_eq!(file.chunks[0].header, ChunkType::Format);
+    }
+
+    #[test]
+    fn repairs_a_missing_pad_byte() {
+        let odd = chunk(b"odd ", 3, b"abc", false);
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"fmt "), odd, data_chunk()]));
+        assert_eq!(
+            repairs,
+            [Repair::MissingPadByte {
+                offset: 36,
+                tag: *b"odd "
+            }]
+        );
+        assert_eq!(file.chunks.len(), 3);
+        assert_eq!(file.chunks[2].data, [1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn drops_a_truncated_chunk() {
+        let list = chunk(b"LIST", 100, b"INFO", false);
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"fmt "), data_chunk(), list]));
+        assert_eq!(
+            repairs,
+            [Repair::TruncatedChunk {
+                offset: 48,
+                tag: *b"LIST",
+                size: 100,
+                available: 4
+            }]
+        );
+        assert_eq!(file.chunks.len(), 2);
+    }
+
+    #[test]
+    fn cuts_the_data_chunk_to_the_audio_present() {
+        let data = chunk(b"data", 100, &[1, 0, 2, 0], false);
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"fmt "), data]));
+        assert_eq!(
+            repairs,
+            [Repair::DataSize {
+                offset: 36,
+                size: 100,
+                available: 4
+            }]
+        );
+        assert_eq!(file.chunks[1].data, [1, 0, 2, 0]);
+    }
+
+    #[test]
+    fn drops_garbage() {
+        let (file, repairs) = repair(wav(&[fmt_chunk(b"fmt "), data_chunk(), b"\0\0\0".to_vec()]));
+        assert_eq!(repairs, [Repair::Garbage { offset: 48, len: 3 }]);
+        assert_eq!(file.chunks.len(), 2);
+    }
+}
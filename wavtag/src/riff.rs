@@ -1,19 +1,99 @@
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 
-use std::fs;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
 use std::io;
-use std::io::{Cursor, Error, ErrorKind, Read, Write};
+use std::io::{BufReader, Cursor, Read, Seek, SeekFrom, Write};
 
-use crate::utils;
+use crate::WavTagError;
+use std::path::{Path, PathBuf};
 
 pub struct RiffChunk {
     pub header: ChunkType,
+
+    /// The tag as it appears in the file, which is written back unchanged even when it isn't
+    /// the canonical tag for `header` (eg. `FMT ` or `list`).
+    pub tag: [u8; 4],
+
     pub data: Vec<u8>,
+
+    /// Where the chunk header was found in the file it was read from.
+    pub offset: Option<u64>,
+
+    /// Set when the chunk was left in the source file by `RiffFile::open_lazy`, in which case
+    /// `data` is empty and the body is copied straight from the source on write.
+    pub deferred: Option<DeferredData>,
+}
+
+/// The location of a chunk body that hasn't been loaded into memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeferredData {
+    /// The offset of the chunk body (after the tag and size) from the start of the file.
+    pub offset: u64,
+    pub len: u64,
+
+    /// The form type of a deferred LIST chunk (eg. `adtl`), read so the chunk can still be found
+    /// by `RiffFile::find_list_chunk_by_type`.
+    pub list_type: Option<[u8; 4]>,
+}
+
+impl DeferredData {
+    fn truncated(&self, chunk: &RiffChunk, available: u64) -> WavTagError {
+        WavTagError::TruncatedChunk {
+            offset: self.offset - 8,
+            tag: chunk.tag,
+            size: self.len,
+            available,
+        }
+    }
 }
 
 impl RiffChunk {
+    pub fn new(header: ChunkType, data: Vec<u8>) -> Self {
+        RiffChunk {
+            tag: header.clone().to_tag(),
+            header,
+            data,
+            offset: None,
+            deferred: None,
+        }
+    }
+
+    /// The size of the chunk body, not including the pad byte after an odd sized body.
     pub fn len(&self) -> usize {
-        self.data.len() // todo: investigate if this is actually valid given we have padded bytes.
+        match self.deferred {
+            Some(deferred) => deferred.len as usize,
+            None => self.data.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The value of the 32-bit size field. In a 64-bit file the data chunk (and anything else too
+    /// large) takes its size from the ds64 chunk.
+    pub(crate) fn size_field(&self, form: RiffForm) -> u32 {
+        let len = self.len();
+        if form != RiffForm::Riff
+            && (self.header == ChunkType::Data || len as u64 > u32::MAX as u64)
+        {
+            u32::MAX
+        } else {
+            len as u32
+        }
+    }
+
+    /// The form type in the first four bytes of a LIST chunk body.
+    pub fn list_type(&self) -> Option<[u8; 4]> {
+        if self.header != ChunkType::List {
+            return None;
+        }
+
+        match self.deferred {
+            Some(deferred) => deferred.list_type,
+            None => self.data.get(..4).and_then(|t| t.try_into().ok()),
+        }
     }
 }
 
@@ -27,9 +107,14 @@ pub enum ChunkType {
     List,
     Label,
     Note,
+    LabelledText,
     Acid,
     Instrument,
     Sampler,
+    Broadcast,
+    Ixml,
+    Wavetable,
+    Id3,
     Unknown(String),
 }
 
@@ -41,12 +126,17 @@ impl ChunkType {
             ChunkType::Fact => *b"fact",
             ChunkType::Cue => *b"cue ",
             ChunkType::Playlist => *b"plst",
-            ChunkType::List => *b"list",
+            ChunkType::List => *b"LIST",
             ChunkType::Label => *b"labl",
             ChunkType::Note => *b"note",
+            ChunkType::LabelledText => *b"ltxt",
             ChunkType::Sampler => *b"smpl",
             ChunkType::Instrument => *b"inst",
             ChunkType::Acid => *b"acid",
+            ChunkType::Broadcast => *b"bext",
+            ChunkType::Ixml => *b"iXML",
+            ChunkType::Wavetable => *b"clm ",
+            ChunkType::Id3 => *b"id3 ",
             ChunkType::Unknown(tag) => {
                 let tag = tag.as_bytes();
                 [tag[0], tag[1], tag[2], tag[3]]
@@ -55,7 +145,12 @@ impl ChunkType {
     }
 }
 
-fn header_to_rifftype(tag: [u8; 4]) -> ChunkType {
+/// Chunks that only reserve space in the file.
+fn is_padding(header: &ChunkType) -> bool {
+    matches!(header, ChunkType::Unknown(tag) if tag == "JUNK" || tag == "junk" || tag == "PAD ")
+}
+
+pub(crate) fn header_to_rifftype(tag: [u8; 4]) -> ChunkType {
     match &tag {
         b"fmt " | b"FMT " => ChunkType::Format,
         b"data" | b"DATA" => ChunkType::Data,
@@ -66,133 +161,956 @@ fn header_to_rifftype(tag: [u8; 4]) -> ChunkType {
         b"labl" | b"LABL" => ChunkType::Label,
         b"note" | b"NOTE" => ChunkType::Note,
         b"smpl" | b"SMPL" => ChunkType::Sampler,
-        b"ltxt" | b"LTXT" | b"INST" | b"inst" => ChunkType::Instrument,
+        b"ltxt" | b"LTXT" => ChunkType::LabelledText,
+        b"inst" | b"INST" => ChunkType::Instrument,
         b"acid" | b"ACID" => ChunkType::Acid,
+        b"bext" | b"BEXT" => ChunkType::Broadcast,
+        b"iXML" | b"IXML" => ChunkType::Ixml,
+        b"clm " | b"CLM " => ChunkType::Wavetable,
+        b"id3 " | b"ID3 " => ChunkType::Id3,
         _ => ChunkType::Unknown(String::from_utf8_lossy(&tag).into_owned()),
     }
 }
 
+/// The container a file was read from. RF64 and BW64 are the 64-bit extensions of RIFF used for
+/// files larger than 4GB, where the real chunk sizes are held in a `ds64` chunk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RiffForm {
+    #[default]
+    Riff,
+    Rf64,
+    Bw64,
+}
+
+impl RiffForm {
+    pub fn to_tag(self) -> [u8; 4] {
+        match self {
+            RiffForm::Riff => *b"RIFF",
+            RiffForm::Rf64 => *b"RF64",
+            RiffForm::Bw64 => *b"BW64",
+        }
+    }
+}
+
+/// Chunk sizes in an RF64 or BW64 file that are too large for their 32-bit size fields, which
+/// are set to 0xFFFFFFFF instead.
+#[derive(Debug, Default)]
+pub(crate) struct DataSize64 {
+    pub(crate) riff_size: u64,
+    data_size: u64,
+    sample_count: u64,
+    table: Vec<([u8; 4], u64)>,
+}
+
+impl DataSize64 {
+    pub(crate) fn from_bytes(data: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(data);
+
+        let mut ds64 = DataSize64 {
+            riff_size: data.read_u64::<LittleEndian>()?,
+            data_size: data.read_u64::<LittleEndian>()?,
+            sample_count: data.read_u64::<LittleEndian>()?,
+            table: Vec::new(),
+        };
+
+        let table_len = data.read_u32::<LittleEndian>()?;
+        for _ in 0..table_len {
+            let mut tag = [0u8; 4];
+            data.read_exact(&mut tag)?;
+            ds64.table.push((tag, data.read_u64::<LittleEndian>()?));
+        }
+
+        Ok(ds64)
+    }
+
+    fn serialise(&self) -> Vec<u8> {
+        let mut chunk = Vec::with_capacity(28 + self.table.len() * 12);
+
+        // writes to a vec are infallible.
+        chunk.write_u64::<LittleEndian>(self.riff_size).unwrap();
+        chunk.write_u64::<LittleEndian>(self.data_size).unwrap();
+        chunk.write_u64::<LittleEndian>(self.sample_count).unwrap();
+        chunk
+            .write_u32::<LittleEndian>(self.table.len() as u32)
+            .unwrap();
+        for (tag, size) in self.table.iter() {
+            chunk.extend_from_slice(tag);
+            chunk.write_u64::<LittleEndian>(*size).unwrap();
+        }
+
+        chunk
+    }
+
+    /// The real size of a chunk whose 32-bit size field is 0xFFFFFFFF.
+    pub(crate) fn chunk_size(&self, tag: [u8; 4]) -> Option<u64> {
+        if &tag == b"data" {
+            return Some(self.data_size);
+        }
+        self.table.iter().find(|(t, _)| *t == tag).map(|(_, s)| *s)
+    }
+}
+
 pub struct RiffFile {
     pub filename: String,
     pub chunks: Vec<RiffChunk>,
+    pub form: RiffForm,
+
+    /// The file that deferred chunks are read from. The file is reopened whenever a deferred
+    /// chunk is needed, so it must not be overwritten while this `RiffFile` is in use.
+    pub source: Option<PathBuf>,
 }
 
 impl RiffFile {
     pub fn len(&self) -> usize {
         // (4 for WAVE header chunk, RIFF chunk not included)
-        4 + self.chunks.iter().fold(0, |acc, &ref chunk| {
-            acc + crate::utils::padded_size(chunk.len() as u32) as usize + 8
-        }) // add 8 bytes for each chunks header
+        4 + self
+            .chunks
+            .iter()
+            .fold(0, |acc, chunk| acc + chunk.len().div_ceil(2) * 2 + 8) // add 8 bytes for each chunks header
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
     }
 
-    pub fn read(mut reader: fs::File, filename: String) -> Result<Self, io::Error> {
+    pub fn read<R: Read + Seek>(reader: R, filename: String) -> Result<Self, WavTagError> {
+        Self::read_chunks(reader, filename, None)
+    }
+
+    /// Read a file without loading the data chunk, or any other chunk larger than `threshold`
+    /// bytes, into memory. Only the position of those chunks is recorded, and their bodies are
+    /// streamed from the file when the `RiffFile` is written.
+    pub fn open_lazy<P: AsRef<Path>>(path: P, threshold: u64) -> Result<Self, WavTagError> {
+        let path = path.as_ref();
+        let filename = path
+            .file_name()
+            .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+
+        let reader = BufReader::new(File::open(path)?);
+        let mut file = Self::read_chunks(reader, filename, Some(threshold))?;
+        file.source = Some(path.to_path_buf());
+
+        Ok(file)
+    }
+
+    fn read_chunks<R: Read + Seek>(
+        mut reader: R,
+        filename: String,
+        threshold: Option<u64>,
+    ) -> Result<Self, WavTagError> {
+        let stream_len = {
+            let start = reader.stream_position()?;
+            let end = reader.seek(SeekFrom::End(0))?;
+            reader.seek(SeekFrom::Start(start))?;
+            end
+        };
+
+        let read_tag = |reader: &mut R| -> Result<[u8; 4], WavTagError> {
+            let mut tag = [0u8; 4];
+            reader.read_exact(&mut tag)?;
+            Ok(tag)
+        };
+
         // don't store stuff like the RIFF header chunk as it'll be regenerated on output
-        {
+        let form = {
             // read RIFF header
-            let mut tag = [0u8; 4]; // header tag
-            reader.read(&mut tag)?;
+            let tag = read_tag(&mut reader)?;
 
-            if &tag != b"RIFF" {
-                return Err(Error::new(ErrorKind::Other, "no RIFF tag found"));
+            match &tag {
+                b"RIFF" => RiffForm::Riff,
+                b"RF64" => RiffForm::Rf64,
+                b"BW64" => RiffForm::Bw64,
+                _ => {
+                    return Err(WavTagError::BadMagic {
+                        offset: 0,
+                        expected: "RIFF, RF64 or BW64",
+                        found: tag,
+                    })
+                }
             }
-        }
+        };
 
         let _ = reader.read_u32::<LittleEndian>()?; // get file length (minus RIFF header).
 
         {
             // read WAVE header
-            let mut tag = [0u8; 4]; // header tag
-            reader.read(&mut tag)?;
+            let tag = read_tag(&mut reader)?;
 
             if &tag != b"WAVE" {
-                return Err(Error::new(ErrorKind::Other, "no WAVE tag found"));
+                return Err(WavTagError::BadMagic {
+                    offset: 8,
+                    expected: "WAVE",
+                    found: tag,
+                });
             }
         }
 
+        // 64-bit files must start with a ds64 chunk, which is regenerated on output too.
+        let ds64 = if form != RiffForm::Riff {
+            let tag = read_tag(&mut reader)?;
+
+            if &tag != b"ds64" {
+                return Err(WavTagError::BadMagic {
+                    offset: 12,
+                    expected: "ds64",
+                    found: tag,
+                });
+            }
+
+            let ds64_len = reader.read_u32::<LittleEndian>()?;
+            let ds64_data = crate::utils::read_bytes(
+                &mut reader,
+                crate::utils::padded_size(ds64_len) as usize,
+            )?;
+            let ds64 =
+                DataSize64::from_bytes(&ds64_data).map_err(|e| WavTagError::MalformedChunk {
+                    chunk: ChunkType::Unknown("ds64".to_string()),
+                    offset: Some(12),
+                    reason: e.to_string(),
+                })?;
+            Some(ds64)
+        } else {
+            None
+        };
+
         let mut chunks = Vec::new();
 
         loop {
             // read chunks
-            // let tag = reader.read_u32::<LittleEndian>()?;
-            let mut tag = [0u8; 4]; // header tag
+            let offset = reader.stream_position()?;
+            if offset >= stream_len {
+                break; // end of file found
+            }
+
+            if stream_len - offset < 8 {
+                break; // trailing bytes too short to be a chunk, which `repair` reports.
+            }
 
-            let read_attempt = reader.read(&mut tag);
+            let tag = read_tag(&mut reader)?;
+            let chunk_len = reader.read_u32::<LittleEndian>()?;
 
-            let chunk_header = match read_attempt {
-                Err(_) => {
-                    println!("malformed RIFF file detected. skipping additional chunks.");
-                    break;
-                }
-                Ok(header) => header,
+            let chunk_len = match &ds64 {
+                Some(ds64) if chunk_len == u32::MAX => match ds64.chunk_size(tag) {
+                    Some(size) => size,
+                    None => {
+                        return Err(WavTagError::InvalidChunkSize {
+                            offset,
+                            tag,
+                            size: chunk_len as u64,
+                        })
+                    }
+                },
+                _ => chunk_len as u64,
             };
 
-            if chunk_header == 0 {
-                break; // end of file found
-            }
+            let header = header_to_rifftype(tag);
 
-            let chunk_len = match reader.read_u32::<LittleEndian>() {
-                Err(_) => {
-                    println!("malformed RIFF file detected. skipping additional chunks.");
-                    break;
+            // a missing pad byte after the last chunk is tolerated. A chunk that runs past the
+            // end of the file is dropped, or cut down to the audio present if it's the data
+            // chunk, keeping the chunks before it. `repair` reports both.
+            let available = stream_len - offset - 8;
+            let chunk_len = if chunk_len <= available {
+                chunk_len
+            } else if header == ChunkType::Data {
+                available
+            } else {
+                break;
+            };
+
+            let defer = match threshold {
+                Some(threshold) => header == ChunkType::Data || chunk_len > threshold,
+                None => false,
+            };
+
+            let mut chunk = if defer {
+                let list_type = if header == ChunkType::List && chunk_len >= 4 {
+                    Some(read_tag(&mut reader)?)
+                } else {
+                    None
+                };
+                reader.seek(SeekFrom::Start(offset + 8 + chunk_len.div_ceil(2) * 2))?;
+
+                let mut chunk = RiffChunk::new(header, Vec::new());
+                chunk.deferred = Some(DeferredData {
+                    offset: offset + 8,
+                    len: chunk_len,
+                    list_type,
+                });
+                chunk
+            } else {
+                let data = crate::utils::read_bytes(&mut reader, chunk_len as usize)?;
+                if chunk_len % 2 != 0 && chunk_len < available {
+                    reader.seek(SeekFrom::Current(1))?; // pad byte
                 }
-                Ok(length) => length,
+                RiffChunk::new(header, data)
             };
 
-            let chunk = Cursor::new(crate::utils::read_bytes(
-                &mut reader,
-                crate::utils::padded_size(chunk_len) as usize,
-            )?);
+            chunk.tag = tag;
+            chunk.offset = Some(offset);
+            chunks.push(chunk);
+        }
+
+        Ok(RiffFile {
+            filename,
+            chunks,
+            form,
+            source: None,
+        })
+    }
 
-            chunks.push(RiffChunk {
-                data: chunk.into_inner(),
-                header: header_to_rifftype(tag),
-            });
+    /// The container the file will be written as. Files that don't fit in a 32-bit RIFF are
+    /// promoted to RF64.
+    pub fn output_form(&self) -> RiffForm {
+        if self.form == RiffForm::Riff && self.len() as u64 > u32::MAX as u64 {
+            RiffForm::Rf64
+        } else {
+            self.form
+        }
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), WavTagError> {
+        let form = self.output_form();
+        self.write_header(&mut writer, form)?;
+
+        // deferred chunks are copied from the source file, opened once for the whole write.
+        let mut source = None;
+
+        for chunk in self.chunks.iter() {
+            self.write_chunk(&mut writer, chunk, form, &mut source)?;
         }
 
-        Ok(RiffFile { filename, chunks })
+        Ok(())
     }
 
-    pub fn validate(&self) -> Result<(), Error> {
+    /// Write the RIFF header, followed by the ds64 chunk in a 64-bit file.
+    pub(crate) fn write_header<W: Write>(
+        &self,
+        writer: &mut W,
+        form: RiffForm,
+    ) -> Result<(), WavTagError> {
+        if form == RiffForm::Riff {
+            // RIFF, WAVE, FMT, DATA chunks
+            writer.write_all(b"RIFF")?; // RIFF tag
+            writer.write_u32::<LittleEndian>(self.len() as u32)?; // file size (not including RIFF chunk of 8 bytes)
+            writer.write_all(b"WAVE")?;
+        } else {
+            let ds64_data = self.data_size_64().serialise();
+
+            writer.write_all(&form.to_tag())?;
+            writer.write_u32::<LittleEndian>(u32::MAX)?; // real size is in the ds64 chunk
+            writer.write_all(b"WAVE")?;
+            writer.write_all(b"ds64")?;
+            writer.write_u32::<LittleEndian>(ds64_data.len() as u32)?;
+            writer.write_all(&ds64_data)?;
+        }
+
         Ok(())
     }
 
-    pub fn write(&self, mut writer: fs::File) -> Result<(), Error> {
-        self.validate()?;
+    /// The ds64 chunk for writing this file as RF64 or BW64.
+    fn data_size_64(&self) -> DataSize64 {
+        let data_size = self
+            .find_chunk_by_type(ChunkType::Data)
+            .map_or(0, |c| c.len());
 
-        // RIFF, WAVE, FMT, DATA chunks
-        writer.write(b"RIFF")?; // RIFF tag
-        writer.write_u32::<LittleEndian>(self.len() as u32)?; // file size (not including RIFF chunk of 8 bytes)
-        writer.write(b"WAVE")?;
+        let mut ds64 = DataSize64 {
+            riff_size: 0,
+            data_size: data_size as u64,
+            sample_count: self.format().map_or(0, |f| f.frame_count(data_size) as u64),
+            table: self
+                .chunks
+                .iter()
+                .filter(|c| c.header != ChunkType::Data && c.len() as u64 > u32::MAX as u64)
+                .map(|c| (c.tag, c.len() as u64))
+                .collect(),
+        };
 
-        for chunk in self.chunks.iter() {
-            let header = chunk.header.clone();
-            let chunk_len = chunk.len() as u32;
-
-            writer.write(&header.to_tag())?;
-            writer.write_u32::<LittleEndian>(chunk.len() as u32)?;
-
-            if utils::padded_size(chunk_len) != chunk_len {
-                // have to copy this chunk to mutably pad it :(
-                let mut padded_chunk_data = chunk.data.clone();
-                utils::pad_vec(
-                    &mut padded_chunk_data,
-                    (utils::padded_size(chunk_len) - chunk_len) as usize,
-                );
-                writer.write(&padded_chunk_data)?;
-            } else {
-                writer.write(&chunk.data)?;
+        // the riff size includes the ds64 chunk itself.
+        ds64.riff_size = (self.len() + 8 + ds64.serialise().len()) as u64;
+
+        ds64
+    }
+
+    fn write_chunk<W: Write>(
+        &self,
+        writer: &mut W,
+        chunk: &RiffChunk,
+        form: RiffForm,
+        source: &mut Option<BufReader<File>>,
+    ) -> Result<(), WavTagError> {
+        let chunk_len = chunk.len();
+
+        writer.write_all(&chunk.tag)?;
+        writer.write_u32::<LittleEndian>(chunk.size_field(form))?;
+
+        match chunk.deferred {
+            Some(deferred) => {
+                if source.is_none() {
+                    *source = Some(self.open_source()?);
+                }
+                let reader = source.as_mut().expect("source to be open");
+                reader.seek(SeekFrom::Start(deferred.offset))?;
+
+                let copied = io::copy(&mut reader.take(deferred.len), writer)?;
+                if copied != deferred.len {
+                    return Err(deferred.truncated(chunk, copied));
+                }
             }
+            None => writer.write_all(&chunk.data)?,
+        }
+
+        if !chunk_len.is_multiple_of(2) {
+            writer.write_all(&[0])?; // pad byte
         }
 
         Ok(())
     }
 
+    /// Write changed metadata back to the source file without copying the audio. The file must
+    /// have been opened with `open_lazy` and its data chunk left alone.
+    ///
+    /// Chunks before the data chunk are rewritten in the space they took up, with a `JUNK` chunk
+    /// filling any space left over. If they no longer fit, everything but the format chunk is
+    /// moved after the data chunk. Chunks after the data chunk are rewritten and the file is
+    /// truncated or extended to fit them.
+    pub fn update_in_place(&mut self) -> Result<(), WavTagError> {
+        let path = match &self.source {
+            Some(path) => path.clone(),
+            None => {
+                return Err(WavTagError::Unsupported(
+                    "file was not opened with open_lazy()".to_string(),
+                ))
+            }
+        };
+
+        let data = match self.find_chunk_by_type(ChunkType::Data) {
+            Some(RiffChunk {
+                deferred: Some(deferred),
+                ..
+            }) => *deferred,
+            Some(_) => {
+                return Err(WavTagError::Unsupported(
+                    "cannot update in place, the data chunk has changed".to_string(),
+                ))
+            }
+            None => return Err(WavTagError::MissingChunk(ChunkType::Data)),
+        };
+
+        if self.output_form() != self.form {
+            return Err(WavTagError::Unsupported(
+                "cannot update in place, the file has grown too large for RIFF".to_string(),
+            ));
+        }
+
+        // the chunks are about to be overwritten, so load anything else still in the file.
+        for i in 0..self.chunks.len() {
+            if self.chunks[i].header != ChunkType::Data && self.chunks[i].deferred.is_some() {
+                let chunk_data = self.read_chunk_data(&self.chunks[i])?.into_owned();
+                self.chunks[i].data = chunk_data;
+                self.chunks[i].deferred = None;
+            }
+        }
+
+        // padding is regenerated to fill whatever space is left.
+        self.chunks.retain(|c| !is_padding(&c.header));
+
+        let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+
+        // the metadata before the data chunk starts after the WAVE tag, or the ds64 chunk.
+        let (start, ds64_len) = if self.form == RiffForm::Riff {
+            (12, 0)
+        } else {
+            file.seek(SeekFrom::Start(16))?;
+            let ds64_len = file.read_u32::<LittleEndian>()?;
+            (20 + crate::utils::padded_size(ds64_len) as u64, ds64_len)
+        };
+
+        let available = data.offset - 8 - start;
+        let fits = |len: u64| {
+            len == available || (len + 8 <= available && (available - len).is_multiple_of(2))
+        };
+
+        let mut before = self.serialise_chunks_before_data()?;
+        if !fits(before.len() as u64) {
+            // everything but the format and fact chunks can live after the audio.
+            let (kept, moved): (Vec<_>, Vec<_>) = self
+                .chunks
+                .drain(..self.data_index())
+                .partition(|c| c.header == ChunkType::Format || c.header == ChunkType::Fact);
+            let data_index = kept.len();
+            self.chunks.splice(..0, kept);
+            self.chunks.splice(data_index + 1..data_index + 1, moved);
+
+            before = self.serialise_chunks_before_data()?;
+            if !fits(before.len() as u64) {
+                return Err(WavTagError::Unsupported(
+                    "cannot update in place, not enough room before the data chunk".to_string(),
+                ));
+            }
+        }
+
+        if (before.len() as u64) < available {
+            let junk = RiffChunk::new(
+                ChunkType::Unknown("JUNK".to_string()),
+                vec![0; (available - before.len() as u64 - 8) as usize],
+            );
+            self.write_chunk(&mut before, &junk, self.form, &mut None)?;
+            let data_index = self.data_index();
+            self.chunks.insert(data_index, junk);
+        }
+
+        let data_index = self.data_index();
+        let mut after = Vec::new();
+        if data.len % 2 != 0 {
+            after.push(0); // pad byte of the data chunk
+        }
+        for chunk in self.chunks[data_index + 1..].iter() {
+            self.write_chunk(&mut after, chunk, self.form, &mut None)?;
+        }
+
+        file.seek(SeekFrom::Start(start))?;
+        file.write_all(&before)?;
+
+        let end = data.offset + data.len;
+        file.seek(SeekFrom::Start(end))?;
+        file.write_all(&after)?;
+        file.set_len(end + after.len() as u64)?;
+
+        if self.form == RiffForm::Riff {
+            file.seek(SeekFrom::Start(4))?;
+            file.write_u32::<LittleEndian>(self.len() as u32)?;
+        } else {
+            let ds64_data = self.data_size_64().serialise();
+            if ds64_data.len() != ds64_len as usize {
+                return Err(WavTagError::Unsupported(
+                    "cannot update in place, the ds64 chunk has changed size".to_string(),
+                ));
+            }
+            file.seek(SeekFrom::Start(20))?;
+            file.write_all(&ds64_data)?;
+        }
+
+        file.flush()?;
+
+        Ok(())
+    }
+
+    fn data_index(&self) -> usize {
+        self.chunks
+            .iter()
+            .position(|c| c.header == ChunkType::Data)
+            .expect("data chunk to exist")
+    }
+
+    fn serialise_chunks_before_data(&self) -> Result<Vec<u8>, WavTagError> {
+        let mut chunks = Vec::new();
+        for chunk in self.chunks[..self.data_index()].iter() {
+            self.write_chunk(&mut chunks, chunk, self.form, &mut None)?;
+        }
+        Ok(chunks)
+    }
+
+    fn open_source(&self) -> Result<BufReader<File>, WavTagError> {
+        match &self.source {
+            Some(path) => Ok(BufReader::new(File::open(path)?)),
+            None => Err(WavTagError::Unsupported(
+                "deferred chunk has no source file".to_string(),
+            )),
+        }
+    }
+
+    /// The body of a chunk, reading it from the source file if it was deferred.
+    pub fn read_chunk_data<'a>(&self, chunk: &'a RiffChunk) -> Result<Cow<'a, [u8]>, WavTagError> {
+        match chunk.deferred {
+            Some(deferred) => {
+                let mut reader = self.open_source()?;
+                reader.seek(SeekFrom::Start(deferred.offset))?;
+                let data = crate::utils::read_bytes(&mut reader, deferred.len as usize)?;
+                if data.len() as u64 != deferred.len {
+                    return Err(deferred.truncated(chunk, data.len() as u64));
+                }
+                Ok(Cow::Owned(data))
+            }
+            None => Ok(Cow::Borrowed(&chunk.data)),
+        }
+    }
+
+    /// A copy of a chunk with its body loaded, for parsing chunks that may have been deferred.
+    pub fn load_chunk(&self, chunk: &RiffChunk) -> Result<RiffChunk, WavTagError> {
+        let mut loaded = RiffChunk::new(chunk.header.clone(), self.read_chunk_data(chunk)?.into());
+        loaded.tag = chunk.tag;
+        loaded.offset = chunk.offset;
+        Ok(loaded)
+    }
+
     pub fn find_chunk_by_type(&self, chunktype: ChunkType) -> Option<&RiffChunk> {
         self.chunks.iter().find(|c| c.header == chunktype)
     }
 
     pub fn add_or_replace_chunk_by_type(&mut self, chunk: RiffChunk) {
-        self.chunks.retain(|c| c.header != chunk.header);
-        self.chunks.push(chunk);
+        let header = chunk.header.clone();
+        self.add_or_replace_chunk(chunk, |c| c.header == header);
+    }
+
+    /// Replace the first chunk matching `matches`, removing any others, so the chunk order of the
+    /// file is kept. The chunk is added to the end if there is no match.
+    fn add_or_replace_chunk<F: Fn(&RiffChunk) -> bool>(&mut self, chunk: RiffChunk, matches: F) {
+        match self.chunks.iter().position(&matches) {
+            Some(index) => {
+                self.chunks[index] = chunk;
+
+                let mut i = 0;
+                self.chunks.retain(|c| {
+                    let keep = i <= index || !matches(c);
+                    i += 1;
+                    keep
+                });
+            }
+            None => self.chunks.push(chunk),
+        }
+    }
+
+    /// LIST chunks are identified by the form type in their first four bytes (eg. `adtl`, `INFO`)
+    /// so a file may legitimately contain several of them.
+    pub fn find_list_chunk_by_type(&self, list_type: [u8; 4]) -> Option<&RiffChunk> {
+        self.chunks
+            .iter()
+            .find(|c| c.list_type() == Some(list_type))
+    }
+
+    pub fn add_or_replace_list_chunk_by_type(&mut self, list_type: [u8; 4], chunk: RiffChunk) {
+        self.add_or_replace_chunk(chunk, |c| c.list_type() == Some(list_type));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AssociatedData, AssociatedDataChunk, CueChunk, CuePoint, FormatTag, InfoChunk, Samples,
+        WaveFormat,
+    };
+
+    /// Append a chunk, with its pad byte, to `bytes`.
+    fn push_chunk(bytes: &mut Vec<u8>, tag: &[u8; 4], size: u32, body: &[u8]) {
+        bytes.extend_from_slice(tag);
+        bytes.write_u32::<LittleEndian>(size).unwrap();
+        bytes.extend_from_slice(body);
+        if !body.len().is_multiple_of(2) {
+            bytes.push(0);
+        }
+    }
+
+    /// A mono 16-bit format chunk.
+    fn fmt_body() -> Vec<u8> {
+        let mut fmt = Vec::new();
+        fmt.write_u16::<LittleEndian>(1).unwrap(); // PCM
+        fmt.write_u16::<LittleEndian>(1).unwrap();
+        fmt.write_u32::<LittleEndian>(44100).unwrap();
+        fmt.write_u32::<LittleEndian>(88200).unwrap();
+        fmt.write_u16::<LittleEndian>(2).unwrap();
+        fmt.write_u16::<LittleEndian>(16).unwrap();
+        fmt
+    }
+
+    #[test]
+    fn reads_rf64() {
+        let audio = [1, 0, 2, 0, 3, 0, 4, 0];
+
+        let mut ds64 = Vec::new();
+        ds64.write_u64::<LittleEndian>(80).unwrap(); // riff size
+        ds64.write_u64::<LittleEndian>(audio.len() as u64).unwrap();
+        ds64.write_u64::<LittleEndian>(4).unwrap(); // sample count
+        ds64.write_u32::<LittleEndian>(0).unwrap(); // table length
+
+        let mut bytes = b"RF64".to_vec();
+        bytes.write_u32::<LittleEndian>(u32::MAX).unwrap();
+        bytes.extend_from_slice(b"WAVE");
+        push_chunk(&mut bytes, b"ds64", ds64.len() as u32, &ds64);
+        push_chunk(&mut bytes, b"fmt ", 16, &fmt_body());
+        push_chunk(&mut bytes, b"data", u32::MAX, &audio);
+        assert_eq!(bytes.len(), 88);
+
+        let file = RiffFile::read(Cursor::new(bytes.clone()), "test.wav".to_string()).unwrap();
+        assert_eq!(file.form, RiffForm::Rf64);
+        assert_eq!(file.chunks.len(), 2);
+        assert_eq!(
+            file.samples().unwrap().samples,
+            Samples::I16(vec![1, 2, 3, 4])
+        );
+
+        let mut out = Vec::new();
+        file.write(&mut out).unwrap();
+        assert_eq!(out, bytes);
+    }
+
+    /// A path in the temp directory for the file of one test.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("wavtag-{}-{}.wav", std::process::id(), name))
+    }
+
+    /// A short mono wav with an adtl list and a comment in an INFO list.
+    fn tagged_wav(comment: &str) -> RiffFile {
+        let mut file = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(FormatTag::Pcm, 1, 44100, 16));
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, (0..=255).collect()));
+        file.set_associated_data_chunk(AssociatedDataChunk {
+            entries: vec![AssociatedData::Label {
+                cue_point_id: 1,
+                text: "Loop 1".to_string(),
+            }],
+        });
+
+        let mut info = InfoChunk::default();
+        info.set_comment(comment);
+        file.set_info_chunk(info);
+
+        file
+    }
+
+    #[test]
+    fn reads_and_writes_through_any_reader_and_writer() {
+        let mut bytes = Vec::new();
+        tagged_wav("generic").write(&mut bytes).unwrap();
+
+        // a borrowed slice in, a fixed size buffer out.
+        let file = RiffFile::read(Cursor::new(bytes.as_slice()), "test.wav".to_string()).unwrap();
+        let mut out = vec![0; bytes.len()];
+        file.write(out.as_mut_slice()).unwrap();
+        assert_eq!(out, bytes);
+        assert!(file.write(&mut out[..bytes.len() - 1]).is_err());
+
+        // a buffered file in, an owned cursor out.
+        let path = temp_path("generic-io");
+        std::fs::write(&path, &bytes).unwrap();
+        let reader = BufReader::new(File::open(&path).unwrap());
+        let file = RiffFile::read(reader, "test.wav".to_string()).unwrap();
+        let mut out = Cursor::new(Vec::new());
+        file.write(&mut out).unwrap();
+        assert_eq!(out.into_inner(), bytes);
+        assert_eq!(file.get_info_chunk().unwrap().comment(), Some("generic"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn finds_deferred_list_chunks() {
+        let path = temp_path("deferred-list");
+        tagged_wav("deferred")
+            .write(File::create(&path).unwrap())
+            .unwrap();
+
+        let mut file = RiffFile::open_lazy(&path, 0).unwrap();
+        assert!(file.chunks.iter().all(|c| c.deferred.is_some()));
+        assert_eq!(file.get_info_chunk().unwrap().comment(), Some("deferred"));
+        assert_eq!(file.get_associated_data_chunk().unwrap().entries.len(), 1);
+
+        let mut info = InfoChunk::default();
+        info.set_comment("replaced");
+        file.set_info_chunk(info);
+        assert_eq!(file.chunks.len(), 4);
+        assert_eq!(file.get_info_chunk().unwrap().comment(), Some("replaced"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parses_deferred_chunks() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../resources/smpl_cue.wav");
+        let eager = RiffFile::read(File::open(path).unwrap(), "smpl_cue.wav".to_string()).unwrap();
+
+        let file = RiffFile::open_lazy(path, 0).unwrap();
+        assert!(file.chunks.iter().all(|c| c.deferred.is_some()));
+        assert_eq!(file.format().unwrap(), eager.format().unwrap());
+        assert_eq!(file.format().unwrap().sample_rate, 44100);
+        let smpl = file.get_sampler_chunk().unwrap();
+        assert_eq!(smpl.sample_loops.len(), 1);
+        assert_eq!(smpl.sample_loops[0].start, 176400);
+        assert_eq!(file.validate(), eager.validate());
+        assert!(file.validate().is_empty());
+    }
+
+    #[test]
+    fn updates_in_place_as_a_chunk_grows_and_shrinks() {
+        let path = temp_path("update-in-place");
+        let mut wav = tagged_wav("short");
+        // the INFO list starts before the data chunk, so growing it moves it after.
+        let info = wav.chunks.pop().unwrap();
+        wav.chunks.insert(1, info);
+        wav.write(File::create(&path).unwrap()).unwrap();
+        let original_len = std::fs::metadata(&path).unwrap().len() as usize;
+
+        let mut lengths = Vec::new();
+        for comment in ["a comment too long to fit before the data chunk", "short"] {
+            let mut file = RiffFile::open_lazy(&path, 0).unwrap();
+            let mut info = file.get_info_chunk().unwrap();
+            info.set_comment(comment);
+            file.set_info_chunk(info);
+            file.update_in_place().unwrap();
+
+            let bytes = std::fs::read(&path).unwrap();
+            let riff_size = (&bytes[4..8]).read_u32::<LittleEndian>().unwrap();
+            assert_eq!(riff_size as usize, bytes.len() - 8);
+
+            let file = RiffFile::read(Cursor::new(&bytes), "test.wav".to_string()).unwrap();
+            assert_eq!(file.get_info_chunk().unwrap().comment(), Some(comment));
+            assert_eq!(
+                file.find_chunk_by_type(ChunkType::Data).unwrap().data,
+                (0..=255).collect::<Vec<u8>>()
+            );
+
+            let data_index = file.data_index();
+            assert!(file.chunks[data_index + 1..]
+                .iter()
+                .any(|c| c.list_type() == Some(*b"INFO")));
+
+            lengths.push(bytes.len());
+        }
+
+        assert!(lengths[0] > original_len);
+        assert!(lengths[1] < lengths[0]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_the_chunks_before_trailing_bytes_or_a_truncated_chunk() {
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        push_chunk(&mut bytes, b"fmt ", 16, &fmt_body());
+        push_chunk(&mut bytes, b"data", 4, &[1, 0, 2, 0]);
+
+        let mut trailing = bytes.clone();
+        trailing.extend_from_slice(b"abc");
+        let file = RiffFile::read(Cursor::new(trailing), "test.wav".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 2);
+
+        let mut truncated = bytes.clone();
+        push_chunk(&mut truncated, b"LIST", 100, b"INFO");
+        let file = RiffFile::read(Cursor::new(truncated), "test.wav".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 2);
+
+        // the audio that's there is kept.
+        bytes.truncate(bytes.len() - 2);
+        let file = RiffFile::read(Cursor::new(bytes), "test.wav".to_string()).unwrap();
+        assert_eq!(
+            file.find_chunk_by_type(ChunkType::Data).unwrap().data,
+            [1, 0]
+        );
+    }
+
+    /// Fill in the RIFF size of a file built with `push_chunk`.
+    fn set_riff_size(bytes: &mut [u8]) {
+        let size = bytes.len() as u32 - 8;
+        (&mut bytes[4..8]).write_u32::<LittleEndian>(size).unwrap();
+    }
+
+    /// Read `bytes` and write them back out.
+    fn round_trip(bytes: &[u8]) -> Vec<u8> {
+        let file = RiffFile::read(Cursor::new(bytes), "test.wav".to_string()).unwrap();
+        let mut out = Vec::new();
+        file.write(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn round_trips_odd_length_chunks() {
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        push_chunk(&mut bytes, b"fmt ", 16, &fmt_body());
+        push_chunk(&mut bytes, b"abcd", 3, b"xyz");
+        push_chunk(&mut bytes, b"data", 4, &[1, 0, 2, 0]);
+        push_chunk(&mut bytes, b"JUNK", 5, &[0; 5]);
+        set_riff_size(&mut bytes);
+
+        let file = RiffFile::read(Cursor::new(&bytes), "test.wav".to_string()).unwrap();
+        assert_eq!(file.chunks.len(), 4);
+        assert_eq!(file.chunks[1].data, b"xyz");
+        assert_eq!(file.chunks[3].len(), 5);
+
+        assert_eq!(round_trip(&bytes), bytes);
+    }
+
+    #[test]
+    fn keeps_non_canonical_tags() {
+        let mut comment = Vec::new();
+        push_chunk(&mut comment, b"ICMT", 3, b"hi\0");
+        let mut info = b"INFO".to_vec();
+        info.extend_from_slice(&comment);
+
+        let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+        push_chunk(&mut bytes, b"FMT ", 16, &fmt_body());
+        push_chunk(&mut bytes, b"data", 4, &[1, 0, 2, 0]);
+        push_chunk(&mut bytes, b"list", info.len() as u32, &info);
+        set_riff_size(&mut bytes);
+
+        let file = RiffFile::read(Cursor::new(&bytes), "test.wav".to_string()).unwrap();
+        assert_eq!(file.chunks[0].header, ChunkType::Format);
+        assert_eq!(file.chunks[2].header, ChunkType::List);
+        assert_eq!(file.format().unwrap().sample_rate, 44100);
+        assert_eq!(file.get_info_chunk().unwrap().comment(), Some("hi"));
+
+        assert_eq!(round_trip(&bytes), bytes);
+    }
+
+    #[test]
+    fn replaces_a_chunk_in_its_place() {
+        let cue = |positions: &[u32]| CueChunk {
+            cue_points: positions
+                .iter()
+                .enumerate()
+                .map(|(i, &position)| CuePoint {
+                    id: i as u32 + 1,
+                    position,
+                    sample_offset: position,
+                    ..CuePoint::default()
+                })
+                .collect(),
+        };
+        let wav = |cue: &CueChunk| {
+            let body = cue.serialise();
+            let mut bytes = b"RIFF\0\0\0\0WAVE".to_vec();
+            push_chunk(&mut bytes, b"fmt ", 16, &fmt_body());
+            push_chunk(&mut bytes, b"cue ", body.len() as u32, &body);
+            push_chunk(&mut bytes, b"data", 4, &[1, 0, 2, 0]);
+            set_riff_size(&mut bytes);
+            bytes
+        };
+
+        let mut file =
+            RiffFile::read(Cursor::new(wav(&cue(&[1]))), "test.wav".to_string()).unwrap();
+        file.set_cue_chunk(cue(&[0, 1]));
+        assert_eq!(file.chunks[1].header, ChunkType::Cue);
+
+        let mut out = Vec::new();
+        file.write(&mut out).unwrap();
+        assert_eq!(out, wav(&cue(&[0, 1])));
+    }
+
+    #[test]
+    fn round_trips_the_resources() {
+        let resources = Path::new(env!("CARGO_MANIFEST_DIR")).join("../resources");
+        let mut count = 0;
+        for entry in std::fs::read_dir(resources).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|e| e != "wav") {
+                continue;
+            }
+            let bytes = std::fs::read(&path).unwrap();
+            assert_eq!(round_trip(&bytes), bytes, "{}", path.display());
+
+            let mut out = Vec::new();
+            RiffFile::open_lazy(&path, 0)
+                .unwrap()
+                .write(&mut out)
+                .unwrap();
+            assert_eq!(out, bytes, "{}", path.display());
+            count += 1;
+        }
+        assert!(count > 0);
     }
 }
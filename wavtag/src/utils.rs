@@ -1,8 +1,9 @@
-use std::fs::File;
 use std::io;
 use std::io::Read;
 
-pub fn read_bytes(ref mut reader: &mut File, n: usize) -> io::Result<Vec<u8>> {
+use crate::WavTagError;
+
+pub fn read_bytes<R: Read>(reader: &mut R, n: usize) -> io::Result<Vec<u8>> {
     // We allocate a runtime fixed size buffer, and we are going to read
     // into it, so zeroing or filling the buffer is a waste. This method
     // is safe, because the contents of the buffer are only exposed when
@@ -11,18 +12,19 @@ pub fn read_bytes(ref mut reader: &mut File, n: usize) -> io::Result<Vec<u8>> {
     // unsafe { buf.set_len(n); }
 
     let mut buf = vec![];
-    io::copy(&mut reader.take(n as u64), &mut buf).expect("file to have enough bytes to read");
+    // the caller checks the length, as a short read means a truncated file.
+    io::copy(&mut reader.take(n as u64), &mut buf)?;
 
     Ok(buf)
 }
 
-static NSTRS: &'static str = "C C#D D#E F F#G G#A A#B ";
+static NSTRS: &str = "C C#D D#E F F#G G#A A#B ";
 
 /// convert a midi note number to a name
 pub fn note_num_to_name(num: u32) -> String {
-    let oct = (num as f32 / 12 as f32).floor() - 2.0;
+    let oct = (num as f32 / 12_f32).floor() - 2.0;
     let nmt = ((num % 12) * 2) as usize;
-    let slice = if NSTRS.as_bytes()[nmt + 1] == ' ' as u8 {
+    let slice = if NSTRS.as_bytes()[nmt + 1] == b' ' {
         &NSTRS[nmt..(nmt + 1)]
     } else {
         &NSTRS[nmt..(nmt + 2)]
@@ -31,7 +33,7 @@ pub fn note_num_to_name(num: u32) -> String {
 }
 
 pub fn padded_size(size: u32) -> u32 {
-    ((size + 1) / 2) * 2
+    size.div_ceil(2) * 2
 }
 
 pub fn pad_vec(v: &mut Vec<u8>, size: usize) {
@@ -41,23 +43,27 @@ pub fn pad_vec(v: &mut Vec<u8>, size: usize) {
     }
 }
 
-pub fn str_to_int(str: &str) -> u32 {
-    str.trim().parse().unwrap()
+pub fn str_to_int(str: &str) -> Result<u32, WavTagError> {
+    str.trim().parse().map_err(|e| WavTagError::InvalidValue {
+        field: "number",
+        reason: format!("{:?} {}", str.trim(), e),
+    })
 }
 
-pub fn dir_as_string(path: &str) -> String {
+pub fn dir_as_string(path: &str) -> Result<String, WavTagError> {
     use std::path::PathBuf;
     let mut current_dir = PathBuf::new();
     if path == "." {
         use std::env;
-        current_dir = env::current_dir().unwrap();
+        current_dir = env::current_dir()?;
     } else {
         current_dir.push(path);
     }
-    current_dir
-        .file_name()
-        .expect("current directory to be valid")
-        .to_string_lossy()
-        .into_owned()
+    match current_dir.file_name() {
+        Some(name) => Ok(name.to_string_lossy().into_owned()),
+        None => Err(WavTagError::InvalidValue {
+            field: "directory",
+            reason: format!("{:?} has no name", path),
+        }),
+    }
 }
-
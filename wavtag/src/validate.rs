@@ -0,0 +1,433 @@
+use std::fmt;
+
+use crate::error::tag_name;
+use crate::{
+    ChunkType, CueChunk, FormatTag, InstrumentChunk, RiffFile, SamplerChunk, WavTagError,
+    WaveFormat,
+};
+
+/// Chunks that may only appear once in a file.
+const SINGLETON_CHUNKS: [ChunkType; 12] = [
+    ChunkType::Format,
+    ChunkType::Data,
+    ChunkType::Fact,
+    ChunkType::Cue,
+    ChunkType::Playlist,
+    ChunkType::Acid,
+    ChunkType::Instrument,
+    ChunkType::Sampler,
+    ChunkType::Broadcast,
+    ChunkType::Ixml,
+    ChunkType::Wavetable,
+    ChunkType::Id3,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Breaks the spec, but most software copes with it.
+    Warning,
+    /// Software is likely to reject the file or misread it.
+    Error,
+}
+
+/// A problem found by `RiffFile::validate`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// There is no format chunk, so the data chunk can't be decoded.
+    MissingFormat,
+
+    /// The format chunk must come before the data chunk so the file can be streamed.
+    FormatAfterData,
+
+    /// A chunk that should only appear once appears `count` times.
+    DuplicateChunk { chunk: ChunkType, count: usize },
+
+    /// A chunk that couldn't be parsed, so its contents weren't checked.
+    MalformedChunk { chunk: ChunkType, reason: String },
+
+    /// The block align doesn't match the channels and bits per sample.
+    BlockAlignMismatch { block_align: u16, expected: u16 },
+
+    /// The byte rate doesn't match the sample rate and block align.
+    ByteRateMismatch { byte_rate: u32, expected: u32 },
+
+    /// A sampler loop that starts or ends past the last sample frame.
+    LoopOutOfRange {
+        id: u32,
+        start: u32,
+        end: u32,
+        frames: usize,
+    },
+
+    /// A sampler loop that doesn't start before it ends.
+    LoopStartAfterEnd { id: u32, start: u32, end: u32 },
+
+    /// A sampler loop whose id doesn't match a cue point.
+    LoopWithoutCuePoint { id: u32 },
+
+    /// The instrument chunk's low note is above its high note.
+    NoteRangeReversed { low_note: u8, high_note: u8 },
+
+    /// The instrument chunk's low velocity is above its high velocity.
+    VelocityRangeReversed { low_vel: u8, high_vel: u8 },
+
+    /// The audio doesn't divide into whole frames of the size given in the clm chunk.
+    WavetableFrameMismatch { frame_size: u32, frames: usize },
+}
+
+impl Diagnostic {
+    fn malformed(chunk: ChunkType, e: WavTagError) -> Self {
+        let reason = match e {
+            WavTagError::MalformedChunk { reason, .. } => reason,
+            e => e.to_string(),
+        };
+        Diagnostic::MalformedChunk { chunk, reason }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::DuplicateChunk { chunk, .. } => match chunk {
+                ChunkType::Format | ChunkType::Data => Severity::Error,
+                _ => Severity::Warning,
+            },
+            Diagnostic::ByteRateMismatch { .. } => Severity::Warning,
+            Diagnostic::LoopWithoutCuePoint { .. } => Severity::Warning,
+            Diagnostic::WavetableFrameMismatch { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+fn chunk_name(chunk: &ChunkType) -> String {
+    format!("{:?}", tag_name(&chunk.clone().to_tag()))
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::MissingFormat => write!(f, "no \"fmt \" chunk found"),
+            Diagnostic::FormatAfterData => {
+                write!(f, "the \"fmt \" chunk comes after the \"data\" chunk")
+            }
+            Diagnostic::DuplicateChunk { chunk, count } => {
+                write!(
+                    f,
+                    "{} {} chunks found, expected one",
+                    count,
+                    chunk_name(chunk)
+                )
+            }
+            Diagnostic::MalformedChunk { chunk, reason } => {
+                write!(f, "malformed {} chunk: {}", chunk_name(chunk), reason)
+            }
+            Diagnostic::BlockAlignMismatch {
+                block_align,
+                expected,
+            } => write!(
+                f,
+                "block align is {} bytes, expected {} bytes",
+                block_align, expected
+            ),
+            Diagnostic::ByteRateMismatch {
+                byte_rate,
+                expected,
+            } => write!(
+                f,
+                "byte rate is {} bytes/s, expected {} bytes/s",
+                byte_rate, expected
+            ),
+            Diagnostic::LoopOutOfRange {
+                id,
+                start,
+                end,
+                frames,
+            } => write!(
+                f,
+                "loop {} ({}-{}) is outside the {} sample frames",
+                id, start, end, frames
+            ),
+            Diagnostic::LoopStartAfterEnd { id, start, end } => {
+                write!(
+                    f,
+                    "loop {} starts at {}, which isn't before its end at {}",
+                    id, start, end
+                )
+            }
+            Diagnostic::LoopWithoutCuePoint { id } => {
+                write!(f, "loop {} has no matching cue point", id)
+            }
+            Diagnostic::NoteRangeReversed {
+                low_note,
+                high_note,
+            } => write!(
+                f,
+                "instrument low note {} is above the high note {}",
+                low_note, high_note
+            ),
+            Diagnostic::VelocityRangeReversed { low_vel, high_vel } => write!(
+                f,
+                "instrument low velocity {} is above the high velocity {}",
+                low_vel, high_vel
+            ),
+            Diagnostic::WavetableFrameMismatch { frame_size, frames } => write!(
+                f,
+                "the {} sample frames don't divide into wavetable frames of {}",
+                frames, frame_size
+            ),
+        }
+    }
+}
+
+fn validate_format(format: &WaveFormat, diagnostics: &mut Vec<Diagnostic>) {
+    // compressed formats have their own block sizes.
+    match format.sample_format() {
+        FormatTag::Pcm | FormatTag::IeeeFloat | FormatTag::ALaw | FormatTag::MuLaw => {}
+        _ => return,
+    }
+
+    let expected = format.channels as u32 * format.bits_per_sample.div_ceil(8) as u32;
+    if format.block_align as u32 != expected {
+        diagnostics.push(Diagnostic::BlockAlignMismatch {
+            block_align: format.block_align,
+            expected: expected as u16,
+        });
+    }
+
+    let expected = format.sample_rate as u64 * format.block_align as u64;
+    if format.byte_rate as u64 != expected {
+        diagnostics.push(Diagnostic::ByteRateMismatch {
+            byte_rate: format.byte_rate,
+            expected: expected as u32,
+        });
+    }
+}
+
+fn validate_sampler(
+    smpl: &SamplerChunk,
+    frames: Option<usize>,
+    cue: &CueChunk,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for sample_loop in smpl.sample_loops.iter() {
+        let (id, start, end) = (sample_loop.id, sample_loop.start, sample_loop.end);
+
+        if start >= end {
+            diagnostics.push(Diagnostic::LoopStartAfterEnd { id, start, end });
+        }
+
+        // the loop end is the last frame played, so it must be inside the data.
+        if let Some(frames) = frames {
+            if start as usize >= frames || end as usize >= frames {
+                diagnostics.push(Diagnostic::LoopOutOfRange {
+                    id,
+                    start,
+                    end,
+                    frames,
+                });
+            }
+        }
+
+        if cue.find_cue_point(id).is_none() {
+            diagnostics.push(Diagnostic::LoopWithoutCuePoint { id });
+        }
+    }
+}
+
+fn validate_instrument(inst: &InstrumentChunk, diagnostics: &mut Vec<Diagnostic>) {
+    if inst.low_note > inst.high_note {
+        diagnostics.push(Diagnostic::NoteRangeReversed {
+            low_note: inst.low_note,
+            high_note: inst.high_note,
+        });
+    }
+
+    if inst.low_vel > inst.high_vel {
+        diagnostics.push(Diagnostic::VelocityRangeReversed {
+            low_vel: inst.low_vel,
+            high_vel: inst.high_vel,
+        });
+    }
+}
+
+impl RiffFile {
+    /// Check the file against the spec, returning every problem found. An empty list means the
+    /// file is valid.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        for chunk in SINGLETON_CHUNKS.iter() {
+            let count = self.chunks.iter().filter(|c| c.header == *chunk).count();
+            if count > 1 {
+                diagnostics.push(Diagnostic::DuplicateChunk {
+                    chunk: chunk.clone(),
+                    count,
+                });
+            }
+        }
+
+        let position = |chunk: ChunkType| self.chunks.iter().position(|c| c.header == chunk);
+        match (position(ChunkType::Format), position(ChunkType::Data)) {
+            (None, _) => diagnostics.push(Diagnostic::MissingFormat),
+            (Some(format), Some(data)) if format > data => {
+                diagnostics.push(Diagnostic::FormatAfterData)
+            }
+            _ => {}
+        }
+
+        let format = match self.format() {
+            Ok(format) => {
+                validate_format(&format, &mut diagnostics);
+                Some(format)
+            }
+            Err(WavTagError::MissingChunk(_)) => None,
+            Err(e) => {
+                diagnostics.push(Diagnostic::malformed(ChunkType::Format, e));
+                None
+            }
+        };
+
+        let frames = match (&format, self.find_chunk_by_type(ChunkType::Data)) {
+            (Some(format), Some(data)) => Some(format.frame_count(data.len())),
+            _ => None,
+        };
+
+        let cue = self.get_cue_chunk().unwrap_or_else(|e| {
+            diagnostics.push(Diagnostic::malformed(ChunkType::Cue, e));
+            CueChunk::default()
+        });
+
+        if self.find_chunk_by_type(ChunkType::Sampler).is_some() {
+            match self.get_sampler_chunk() {
+                Ok(smpl) => validate_sampler(&smpl, frames, &cue, &mut diagnostics),
+                Err(e) => diagnostics.push(Diagnostic::malformed(ChunkType::Sampler, e)),
+            }
+        }
+
+        if self.find_chunk_by_type(ChunkType::Instrument).is_some() {
+            match self.get_instrument_chunk() {
+                Ok(inst) => validate_instrument(&inst, &mut diagnostics),
+                Err(e) => diagnostics.push(Diagnostic::malformed(ChunkType::Instrument, e)),
+            }
+        }
+
+        if self.find_chunk_by_type(ChunkType::Wavetable).is_some() {
+            match self.get_wavetable_chunk() {
+                Ok(clm) => {
+                    if let Some(frames) = frames {
+                        if clm.frame_size == 0 || frames % clm.frame_size as usize != 0 {
+                            diagnostics.push(Diagnostic::WavetableFrameMismatch {
+                                frame_size: clm.frame_size,
+                                frames,
+                            });
+                        }
+                    }
+                }
+                Err(e) => diagnostics.push(Diagnostic::malformed(ChunkType::Wavetable, e)),
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Whether validation found anything with error severity.
+    pub fn is_valid(&self) -> bool {
+        self.validate()
+            .iter()
+            .all(|d| d.severity() != Severity::Error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LoopType, RiffChunk, RiffForm, SampleLoop};
+
+    /// 100 frames of silent mono 16-bit audio.
+    fn wav() -> RiffFile {
+        let mut file = RiffFile {
+            filename: "test.wav".to_string(),
+            chunks: Vec::new(),
+            form: RiffForm::Riff,
+            source: None,
+        };
+        file.set_format(WaveFormat::new(FormatTag::Pcm, 1, 44100, 16));
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, vec![0; 200]));
+        file
+    }
+
+    fn errors(file: &RiffFile) -> Vec<Diagnostic> {
+        file.validate()
+            .into_iter()
+            .filter(|d| d.severity() == Severity::Error)
+            .collect()
+    }
+
+    #[test]
+    fn passes_a_good_file() {
+        assert_eq!(wav().validate(), []);
+    }
+
+    #[test]
+    fn finds_chunks_out_of_order_or_repeated() {
+        let mut file = wav();
+        file.chunks.reverse();
+        assert_eq!(errors(&file), [Diagnostic::FormatAfterData]);
+
+        let mut file = wav();
+        file.chunks
+            .push(RiffChunk::new(ChunkType::Data, vec![0; 2]));
+        assert_eq!(
+            errors(&file),
+            [Diagnostic::DuplicateChunk {
+                chunk: ChunkType::Data,
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_a_bad_block_align() {
+        let mut file = wav();
+        let mut format = file.format().unwrap();
+        format.block_align = 4;
+        file.set_format(format);
+
+        assert_eq!(
+            errors(&file),
+            [Diagnostic::BlockAlignMismatch {
+                block_align: 4,
+                expected: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_a_loop_past_the_end() {
+        let mut file = wav();
+        file.set_sampler_chunk(SamplerChunk {
+            sample_loops: vec![SampleLoop {
+                id: 0,
+                loop_type: LoopType::Forward,
+                start: 0,
+                end: 100,
+                fraction: 0,
+                play_count: 0,
+            }],
+            ..SamplerChunk::default()
+        });
+
+        assert_eq!(
+            errors(&file),
+            [Diagnostic::LoopOutOfRange {
+                id: 0,
+                start: 0,
+                end: 100,
+                frames: 100
+            }]
+        );
+        assert!(file
+            .validate()
+            .contains(&Diagnostic::LoopWithoutCuePoint { id: 0 }));
+    }
+}
@@ -0,0 +1,253 @@
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use std::io;
+use std::io::{Cursor, Read};
+
+use crate::{FlacBlock, FlacBlockType, FlacFile, LoopType, SampleLoop, WavTagError};
+
+/// The `KEY=value` tags of Ogg Vorbis, Opus and FLAC files. Keys are case insensitive and may
+/// appear more than once.
+///
+/// Loop points are kept in the `LOOPSTART` and `LOOPLENGTH` comments, in sample frames, as used
+/// by game engines. Some files use `LOOPEND` instead of the length, which is the frame after the
+/// last one in the loop.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VorbisComments {
+    pub vendor: String,
+    pub comments: Vec<(String, String)>,
+}
+
+impl VorbisComments {
+    /// Read the comments from the body of a comment header, after any codec specific magic.
+    pub(crate) fn read(body: &[u8]) -> Result<Self, io::Error> {
+        let mut data = Cursor::new(body);
+
+        let read_string = |data: &mut Cursor<&[u8]>| -> Result<String, io::Error> {
+            let len = data.read_u32::<LittleEndian>()? as usize;
+            // a corrupt length fails the read rather than allocating more than the body.
+            let mut text = vec![0u8; len.min(body.len())];
+            data.read_exact(&mut text)?;
+            Ok(String::from_utf8_lossy(&text).into_owned())
+        };
+
+        let vendor = read_string(&mut data)?;
+        let num_comments = data.read_u32::<LittleEndian>()?;
+
+        let mut comments = Vec::new();
+        for _ in 0..num_comments {
+            let comment = read_string(&mut data)?;
+            // comments without a separator are ignored, as most readers do.
+            if let Some((key, value)) = comment.split_once('=') {
+                comments.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        Ok(VorbisComments { vendor, comments })
+    }
+
+    pub fn serialise(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        // writes to a vec are infallible.
+        data.write_u32::<LittleEndian>(self.vendor.len() as u32)
+            .unwrap();
+        data.extend_from_slice(self.vendor.as_bytes());
+        data.write_u32::<LittleEndian>(self.comments.len() as u32)
+            .unwrap();
+
+        for (key, value) in self.comments.iter() {
+            data.write_u32::<LittleEndian>((key.len() + 1 + value.len()) as u32)
+                .unwrap();
+            data.extend_from_slice(key.as_bytes());
+            data.push(b'=');
+            data.extend_from_slice(value.as_bytes());
+        }
+
+        data
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Every value of a key, in order.
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a str> {
+        self.comments
+            .iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Replace the first comment with this key, removing any others, or add it to the end.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self
+            .comments
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(key))
+        {
+            Some(index) => {
+                self.comments[index].1 = value.to_string();
+
+                let mut i = 0;
+                self.comments.retain(|(k, _)| {
+                    let keep = i <= index || !k.eq_ignore_ascii_case(key);
+                    i += 1;
+                    keep
+                });
+            }
+            None => self.comments.push((key.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.comments.retain(|(k, _)| !k.eq_ignore_ascii_case(key));
+    }
+
+    fn get_frame(&self, key: &str) -> Option<u32> {
+        self.get(key).and_then(|v| v.trim().parse().ok())
+    }
+
+    /// The loop in the `LOOPSTART` and `LOOPLENGTH` or `LOOPEND` comments, in the form the smpl
+    /// chunk uses. A loop with only a start runs to the end of the audio, which is `frames` long.
+    pub fn loops(&self, frames: u64) -> Vec<SampleLoop> {
+        let start = match self.get_frame("LOOPSTART") {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+
+        // the smpl chunk's end is the last frame played.
+        let end = match (self.get_frame("LOOPLENGTH"), self.get_frame("LOOPEND")) {
+            (Some(length), _) if length > 0 => start.saturating_add(length - 1),
+            (_, Some(end)) if end > start => end - 1,
+            _ if frames > start as u64 => (frames - 1).min(u32::MAX as u64) as u32,
+            _ => return Vec::new(),
+        };
+
+        vec![SampleLoop {
+            id: 0,
+            loop_type: LoopType::Forward,
+            start,
+            end,
+            fraction: 0,
+            play_count: 0,
+        }]
+    }
+
+    /// Write the first loop to the loop comments, or remove them if there are no loops. Only one
+    /// loop can be stored. `LOOPEND` is only updated if the file already uses it.
+    pub fn set_loops(&mut self, loops: &[SampleLoop]) {
+        let sample_loop = match loops.first() {
+            Some(sample_loop) if sample_loop.end >= sample_loop.start => sample_loop,
+            _ => {
+                self.remove("LOOPSTART");
+                self.remove("LOOPLENGTH");
+                self.remove("LOOPEND");
+                return;
+            }
+        };
+
+        let length = sample_loop.end as u64 - sample_loop.start as u64 + 1;
+        self.set("LOOPSTART", &sample_loop.start.to_string());
+        self.set("LOOPLENGTH", &length.to_string());
+        if self.get("LOOPEND").is_some() {
+            self.set("LOOPEND", &(sample_loop.end as u64 + 1).to_string());
+        }
+    }
+}
+
+impl VorbisComments {
+    pub fn from_block(block: &FlacBlock) -> Result<Self, WavTagError> {
+        block.check_type(FlacBlockType::VorbisComment)?;
+
+        Self::read(&block.data).map_err(|e| block.body_error(e))
+    }
+}
+
+impl FlacFile {
+    pub fn get_vorbis_comments(&self) -> Result<VorbisComments, WavTagError> {
+        match self.find_block(FlacBlockType::VorbisComment) {
+            Some(b) => VorbisComments::from_block(b),
+            None => Ok(VorbisComments::default()),
+        }
+    }
+
+    /// Replace the comments block, or add it after the stream info.
+    pub fn set_vorbis_comments(&mut self, comments: VorbisComments) {
+        let block = FlacBlock::new(FlacBlockType::VorbisComment, comments.serialise());
+        match self
+            .blocks
+            .iter()
+            .position(|b| b.block_type == FlacBlockType::VorbisComment)
+        {
+            Some(index) => self.blocks[index] = block,
+            None => {
+                let index = self.blocks.len().min(1);
+                self.blocks.insert(index, block);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comments(pairs: &[(&str, &str)]) -> VorbisComments {
+        VorbisComments {
+            vendor: "test".to_string(),
+            comments: pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reads_loop_comments() {
+        let looped = |pairs| {
+            comments(pairs)
+                .loops(1000)
+                .first()
+                .map(|l| (l.start, l.end))
+        };
+
+        assert_eq!(
+            looped(&[("LOOPSTART", "10"), ("LOOPLENGTH", "90")]),
+            Some((10, 99))
+        );
+        assert_eq!(
+            looped(&[("loopstart", "10"), ("LOOPEND", "100")]),
+            Some((10, 99))
+        );
+        assert_eq!(looped(&[("LOOPSTART", "10")]), Some((10, 999)));
+        assert_eq!(looped(&[("LOOPSTART", "1000")]), None);
+        assert_eq!(looped(&[("LOOPLENGTH", "90")]), None);
+    }
+
+    #[test]
+    fn writes_loop_comments() {
+        let sample_loop = SampleLoop {
+            id: 0,
+            loop_type: LoopType::Forward,
+            start: 10,
+            end: 99,
+            fraction: 0,
+            play_count: 0,
+        };
+
+        let mut with_end = comments(&[("LOOPEND", "5"), ("TITLE", "kick")]);
+        with_end.set_loops(&[sample_loop]);
+        assert_eq!(with_end.get("LOOPSTART"), Some("10"));
+        assert_eq!(with_end.get("LOOPLENGTH"), Some("90"));
+        assert_eq!(with_end.get("LOOPEND"), Some("100"));
+
+        let read = VorbisComments::read(&with_end.serialise()).unwrap();
+        assert_eq!(read, with_end);
+
+        with_end.set_loops(&[]);
+        assert_eq!(with_end, comments(&[("TITLE", "kick")]));
+    }
+}
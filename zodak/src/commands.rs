@@ -1,6 +1,11 @@
 use docopt::Docopt;
 use wavtag::utils::*;
-use wavtag::{ChunkType, InstrumentChunk, LoopType, RiffFile, SampleLoop, SamplerChunk};
+use wavtag::{
+    AcidChunk, AiffFile, AiffInstrumentChunk, AssociatedDataChunk, BextChunk, ChunkType,
+    CommentsChunk, CommonChunk, CueChunk, CuePoint, FlacFile, InfoChunk, InstrumentChunk,
+    IxmlChunk, LoopType, MarkerChunk, OggFile, RiffFile, SampleLoop, SamplerChunk, Severity,
+    WavTagError, WaveFormat, WavetableChunk,
+};
 
 use std::fs;
 use std::io;
@@ -10,6 +15,42 @@ use std::path::{Path, PathBuf};
 use crate::midi::name_to_note_num;
 use crate::{USAGE, VERSION};
 
+/// Chunks larger than this (and every data chunk) are streamed from the source file rather than
+/// loaded into memory.
+const LAZY_THRESHOLD: u64 = 1024 * 1024;
+
+/// The file formats samples can be written as.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Wav,
+    Aiff,
+    Flac,
+
+    /// Only the comments of an ogg source are rewritten, the audio isn't re-encoded.
+    Ogg,
+}
+
+impl OutputFormat {
+    fn from_arg(arg: &str) -> Option<Self> {
+        match arg {
+            "wav" => Some(OutputFormat::Wav),
+            "aif" | "aiff" => Some(OutputFormat::Aiff),
+            "flac" => Some(OutputFormat::Flac),
+            "ogg" => Some(OutputFormat::Ogg),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Aiff => "aif",
+            OutputFormat::Flac => "flac",
+            OutputFormat::Ogg => "ogg",
+        }
+    }
+}
+
 pub struct DestinationSample {
     pub output_filename: String,
     pub file: RiffFile,
@@ -33,51 +74,178 @@ pub fn run() -> io::Result<()> {
     let _arg_verbose = args.get_str("--verbose");
 
     if args.get_bool("print") {
-        let sourcedir = args.get_vec("<source>")[0];
+        let source = Path::new(args.get_vec("<source>")[0]);
 
-        match read_directory(Path::new(sourcedir).to_path_buf()) {
-            Ok(wavs) => {
-                for wav in wavs {
-                    print_wav(wav)
+        match audio_paths(
+            source,
+            &["wav", "aif", "aiff", "flac", "ogg", "oga", "opus"],
+        ) {
+            Ok(paths) => {
+                for path in paths {
+                    let result = match path.extension().and_then(|oss| oss.to_str()) {
+                        Some("aif") | Some("aiff") => read_aiff(&path).map(print_aiff),
+                        Some("ogg") | Some("oga") | Some("opus") => read_ogg(&path).map(print_ogg),
+                        _ => open_sample(&path).map(print_wav),
+                    };
+                    if let Err(e) = result {
+                        println!("Error reading \"{}\": {}", path.display(), e);
+                    }
                 }
             }
-            Err(e) => println!("Error: {}", e), // todo: properly unwrap error message.
+            Err(e) => println!("Error: {}", e),
         }
     }
 
+    if args.get_bool("repair") {
+        let source = Path::new(args.get_vec("<source>")[0]);
+        let dest_path = Path::new(args.get_vec("<destdir>")[0]);
+
+        if !dest_path.is_dir() {
+            println!("\nError: Supplied output path is not a directory.");
+            std::process::exit(1);
+        }
+
+        match audio_paths(source, &["wav"]) {
+            Ok(paths) => {
+                for path in paths {
+                    repair_wav(&path, dest_path)
+                }
+            }
+            Err(e) => println!("Error: {}", e),
+        }
+    }
+
+    if args.get_bool("wavetable") {
+        let source = Path::new(args.get_vec("<source>")[0]);
+        let dest_file = Path::new(args.get_str("<destfile>"));
+        let frame_size = int_or_exit(args.get_str("--frame-size"));
+        build_wavetable(source, dest_file, frame_size);
+    }
+
     if args.get_bool("tag") {
         let source_arg = args.get_vec("<source>")[0];
-        let destdir = args.get_vec("<destdir>")[0];
+        let in_place = args.get_bool("--in-place");
         let mut dest_path = PathBuf::new();
-        dest_path.push(destdir);
+        if !in_place {
+            dest_path.push(args.get_vec("<destdir>")[0]);
+        }
 
         use std::process::exit;
-        if !dest_path.is_dir() {
+        if !in_place && !dest_path.is_dir() {
             println!("\nError: Supplied output path is not a directory.");
             exit(1);
         }
 
+        let output_format = match OutputFormat::from_arg(args.get_str("--format")) {
+            Some(format) => format,
+            None => {
+                println!(
+                    "\nError: unknown output format {:?}, expected wav, aiff, flac or ogg.",
+                    args.get_str("--format")
+                );
+                exit(1);
+            }
+        };
+        if in_place && (output_format != OutputFormat::Wav || args.get_bool("--pcm")) {
+            println!("\nError: files edited in place can't change format.");
+            exit(1);
+        }
+
+        // tuning and gain apply to every sample in the instrument.
+        let (fine_tune, gain) = {
+            fn parse_i8(args: &docopt::ArgvMap, key: &str) -> i8 {
+                if !args.get_bool(key) {
+                    return 0;
+                }
+                args.get_str(key).trim().parse().unwrap_or_else(|_| {
+                    println!("\nError: {} must be a whole number.", key);
+                    exit(1);
+                })
+            }
+
+            let inst = InstrumentChunk {
+                fine_tune: parse_i8(&args, "--fine-tune"),
+                gain: parse_i8(&args, "--gain"),
+                ..InstrumentChunk::default()
+            };
+
+            if let Err(e) = inst.validate() {
+                println!("\nError: {}.", e);
+                exit(1);
+            }
+
+            (inst.fine_tune, inst.gain)
+        };
+
         let mut source = PathBuf::new();
         source.push(source_arg);
         let file_result = if source.is_dir() {
             read_directory(source.clone())
         } else {
-            let file = fs::File::open(source.clone()).expect("file to open");
-            let filename: String = (*source
-                .file_name()
-                .expect("filename to be acceptable")
-                .to_string_lossy())
-            .to_string();
-            let wav = RiffFile::read(file, filename);
+            let wav = open_sample(&source);
             match wav {
                 Ok(w) => Ok(vec![w]),
-                Err(e) => panic!("Error reading wav: {}", e),
+                Err(e) => {
+                    println!("Error reading \"{}\": {}. skipping.", source.display(), e);
+                    Ok(vec![])
+                }
             }
         };
 
+        // ogg output is written from the source files.
+        let source_dir = if source.is_dir() {
+            source.clone()
+        } else {
+            source.parent().map_or_else(PathBuf::new, Path::to_path_buf)
+        };
+
         match file_result {
-            Ok(wavs) => {
-                println!("Found {} wav files.", wavs.len());
+            Ok(mut wavs) => {
+                println!("Found {} audio files.", wavs.len());
+
+                // converted files have no wav to edit.
+                if in_place && wavs.iter().any(|w| w.source.is_none()) {
+                    println!("\nError: only wav sources can be edited in place.");
+                    exit(1);
+                }
+
+                // ogg audio can't be encoded, so only ogg sources can have their comments tagged.
+                let is_ogg = |w: &RiffFile| {
+                    Path::new(&w.filename)
+                        .extension()
+                        .and_then(|oss| oss.to_str())
+                        .is_some_and(|ext| ext == "ogg" || ext == "oga")
+                };
+                if output_format == OutputFormat::Ogg && !wavs.iter().all(is_ogg) {
+                    println!("\nError: only ogg sources can be written as ogg.");
+                    exit(1);
+                }
+
+                // most modern samplers only load linear PCM. files edited in place keep their
+                // format, and aiff and flac output is always transcoded as it can only hold PCM.
+                let compressed = wavs
+                    .iter()
+                    .filter(|w| w.format().is_ok_and(|f| f.is_compressed()))
+                    .count();
+                if compressed > 0 && !in_place {
+                    let transcode =
+                        matches!(output_format, OutputFormat::Aiff | OutputFormat::Flac)
+                            || args.get_bool("--pcm")
+                            || get_input(&format!(
+                            "transcode {} µ-law, A-law or IMA ADPCM files to 16-bit PCM? [y/N]: ",
+                            compressed
+                        ))
+                            .eq_ignore_ascii_case("y");
+
+                    if transcode {
+                        for wav in wavs.iter_mut() {
+                            if let Err(e) = wav.transcode_to_pcm() {
+                                println!("\nError transcoding {}: {}.", wav.filename, e);
+                                exit(1);
+                            }
+                        }
+                    }
+                }
 
                 // prompt for an instrument name.
                 let instrument_name_default = dir_as_string(
@@ -89,7 +257,7 @@ pub fn run() -> io::Result<()> {
                 ); // todo: clip last directory name
                 let mut instrument_name =
                     get_input(format!("instrument name [{}]: ", instrument_name_default).as_str());
-                if instrument_name == "" {
+                if instrument_name.is_empty() {
                     instrument_name = instrument_name_default
                 };
 
@@ -111,13 +279,18 @@ pub fn run() -> io::Result<()> {
                     name_to_note_num(&capture[capture.len() - 1])
                 }
 
-                fn output_filename(instrument_name: String, keyname: String) -> String {
-                    format!("{} {}.wav", instrument_name, keyname)
+                fn output_filename(
+                    instrument_name: String,
+                    keyname: String,
+                    extension: &str,
+                ) -> String {
+                    format!("{} {}.{}", instrument_name, keyname, extension)
                 }
 
                 fn guess_defaults(
                     wavs: Vec<RiffFile>,
                     instrument_name: String,
+                    output_format: OutputFormat,
                     args: &docopt::ArgvMap,
                 ) -> Vec<DestinationSample> {
                     let mut files_to_write = Vec::new();
@@ -125,17 +298,25 @@ pub fn run() -> io::Result<()> {
                     for wav in wavs {
                         let unity_note_number = key_from_filename(wav.filename.as_str());
                         let unity_note_name = note_num_to_name(unity_note_number as u32);
-                        let output_filename =
-                            output_filename(instrument_name.clone(), unity_note_name);
+                        // files edited in place keep their names.
+                        let output_filename = if args.get_bool("--in-place") {
+                            wav.filename.clone()
+                        } else {
+                            output_filename(
+                                instrument_name.clone(),
+                                unity_note_name,
+                                output_format.extension(),
+                            )
+                        };
 
                         let loop_start: u32 = if args.get_bool("--loop-start") {
-                            str_to_int(args.get_str("--loop-start"))
+                            int_or_exit(args.get_str("--loop-start"))
                         } else {
                             0
                         };
 
                         let loop_end: u32 = if args.get_bool("--loop-end") {
-                            str_to_int(args.get_str("--loop-end"))
+                            int_or_exit(args.get_str("--loop-end"))
                         } else {
                             0
                         };
@@ -153,18 +334,19 @@ pub fn run() -> io::Result<()> {
                         });
                     }
 
-                    files_to_write.sort_by(|a, b| a.unity_note.cmp(&b.unity_note));
+                    files_to_write.sort_by_key(|a| a.unity_note);
 
                     // we want to sort first THEN ask this info (easier for user).
                     if args.get_bool("--smpl")
+                        && !args.get_bool("--one-shot")
                         && (!args.get_bool("--loop-start") || !args.get_bool("--loop-end"))
                     {
                         // found a situation we need to ask for more information.
 
                         for wav in files_to_write.iter_mut() {
                             println!("\n{}:", wav.file.filename);
-                            wav.loop_start = str_to_int(&get_input("loop start (0-4294967294): "));
-                            wav.loop_end = str_to_int(&get_input("loop end (0-4294967294): "));
+                            wav.loop_start = int_or_exit(&get_input("loop start (0-4294967294): "));
+                            wav.loop_end = int_or_exit(&get_input("loop end (0-4294967294): "));
                         }
                     }
 
@@ -190,7 +372,8 @@ pub fn run() -> io::Result<()> {
                     files_to_write
                 }
 
-                let defaults = guess_defaults(wavs, instrument_name, &args);
+                let mut defaults =
+                    guess_defaults(wavs, instrument_name.clone(), output_format, &args);
 
                 println!("FILES WRITTEN:");
                 print!("{:<40}", "Input");
@@ -198,10 +381,10 @@ pub fn run() -> io::Result<()> {
                 print!("{:<15}", "Note");
                 print!("{:<15}", "KeyRange");
                 print!("{:<15}", "VelRange");
-                print!("\n");
+                println!();
 
                 // iterate our guessed defaults, correcting any unwanted info
-                for mut wav in defaults {
+                for wav in defaults.iter_mut() {
                     print!("{:<40}", wav.file.filename);
                     print!("{:<40}", wav.output_filename);
                     print!(
@@ -221,13 +404,13 @@ pub fn run() -> io::Result<()> {
                         )
                     );
                     print!("{:<15}", format!("{}-{}", wav.lovel, wav.hivel));
-                    print!("\n");
+                    println!();
 
                     if args.get_bool("--inst") {
                         wav.file.set_instrument_chunk(InstrumentChunk {
                             unshifted_note: wav.unity_note,
-                            fine_tune: 0,
-                            gain: 0,
+                            fine_tune,
+                            gain,
                             low_note: wav.lokey,
                             high_note: wav.hikey,
                             low_vel: wav.lovel,
@@ -235,25 +418,152 @@ pub fn run() -> io::Result<()> {
                         });
                     }
 
+                    if args.get_bool("--acid") {
+                        let mut acid = wav.file.get_acid_chunk().unwrap_or_else(|e| {
+                            println!("\n{}: {}, replacing it.", wav.file.filename, e);
+                            AcidChunk::default()
+                        });
+
+                        acid.root_note = wav.unity_note as u16;
+                        acid.root_note_set = true;
+                        acid.one_shot = args.get_bool("--one-shot");
+                        acid.stretch = !acid.one_shot;
+
+                        if args.get_bool("--tempo") {
+                            acid.tempo =
+                                args.get_str("--tempo").trim().parse().unwrap_or_else(|_| {
+                                    println!("\nError: --tempo must be a number.");
+                                    exit(1);
+                                });
+                        }
+
+                        if args.get_bool("--beats") {
+                            acid.beats = int_or_exit(args.get_str("--beats"));
+                        } else if args.get_bool("--tempo") {
+                            // work out the number of beats from the length of the sample.
+                            if let (Ok(format), Some(data)) = (
+                                wav.file.format(),
+                                wav.file.find_chunk_by_type(ChunkType::Data),
+                            ) {
+                                let seconds = format.frame_count(data.len()) as f32
+                                    / format.sample_rate as f32;
+                                acid.beats = (seconds * acid.tempo / 60.0).round() as u32;
+                            }
+                        }
+
+                        wav.file.set_acid_chunk(acid);
+                    }
+
+                    // the instrument name is written as the title unless overridden.
+                    let mut info = wav.file.get_info_chunk().unwrap_or_else(|e| {
+                        println!("\n{}: {}, replacing it.", wav.file.filename, e);
+                        InfoChunk::default()
+                    });
+                    if args.get_bool("--title") {
+                        info.set_title(args.get_str("--title"));
+                    } else {
+                        info.set_title(&instrument_name);
+                    }
+                    if args.get_bool("--artist") {
+                        info.set_artist(args.get_str("--artist"));
+                    }
+                    if args.get_bool("--comment") {
+                        info.set_comment(args.get_str("--comment"));
+                    }
+                    if args.get_bool("--keywords") {
+                        info.set_keywords(args.get_str("--keywords"));
+                    }
+                    wav.file.set_info_chunk(info);
+
+                    if args.get_bool("--sync-id3") {
+                        if let Err(e) = wav.file.sync_id3() {
+                            println!("\n{}: {}, not syncing id3.", wav.file.filename, e);
+                        }
+                    }
+
+                    if args.get_bool("--originator") {
+                        let mut bext = wav.file.get_bext_chunk().unwrap_or_else(|e| {
+                            println!("\n{}: {}, replacing it.", wav.file.filename, e);
+                            BextChunk::default()
+                        });
+                        bext.originator = args.get_str("--originator").to_string();
+                        wav.file.set_bext_chunk(bext);
+                    }
+
                     if args.get_bool("--smpl") {
+                        let sample_period = wav
+                            .file
+                            .format()
+                            .map(|format| format.sample_period())
+                            .unwrap_or(0);
+
                         wav.file.set_sampler_chunk(SamplerChunk {
                             manufacturer: 0,
                             product: 0,
-                            sample_period: 10,
+                            sample_period,
                             midi_unity_note: wav.unity_note as u32,
                             midi_pitch_fraction: 0,
                             smpte_format: 0,
                             smpte_offset: 0,
-                            sample_loops: vec![SampleLoop {
-                                id: 0,
-                                loop_type: LoopType::Forward,
-                                start: wav.loop_start,
-                                end: wav.loop_end,
-                                fraction: 0,
-                                play_count: 0,
-                            }],
+                            // one-shots have no loops.
+                            sample_loops: if args.get_bool("--one-shot") {
+                                Vec::new()
+                            } else {
+                                vec![SampleLoop {
+                                    id: 0,
+                                    loop_type: LoopType::Forward,
+                                    start: wav.loop_start,
+                                    end: wav.loop_end,
+                                    fraction: 0,
+                                    play_count: 0,
+                                }]
+                            },
                             sampler_data: Vec::new(),
                         });
+
+                        // loops refer to a cue point at their start.
+                        if !args.get_bool("--one-shot") {
+                            let mut cue = wav.file.get_cue_chunk().unwrap_or_else(|e| {
+                                println!("\n{}: {}, replacing it.", wav.file.filename, e);
+                                CueChunk::default()
+                            });
+                            cue.cue_points.retain(|c| c.id != 0);
+                            cue.cue_points.push(CuePoint {
+                                id: 0,
+                                position: wav.loop_start,
+                                sample_offset: wav.loop_start,
+                                ..CuePoint::default()
+                            });
+                            wav.file.set_cue_chunk(cue);
+                        }
+                    }
+                }
+
+                // files that fail to validate are skipped, the rest are still written.
+                defaults.retain(|wav| {
+                    let mut valid = true;
+                    for diagnostic in wav.file.validate() {
+                        let severity = match diagnostic.severity() {
+                            Severity::Warning => "Warning",
+                            Severity::Error => {
+                                valid = false;
+                                "Error"
+                            }
+                        };
+                        println!("{}: {}: {}.", wav.output_filename, severity, diagnostic);
+                    }
+                    if !valid {
+                        println!("{}: failed to validate, skipping.", wav.output_filename);
+                    }
+                    valid
+                });
+
+                for mut wav in defaults {
+                    if in_place {
+                        if let Err(e) = wav.file.update_in_place() {
+                            println!("\nError updating {}: {}.", wav.file.filename, e);
+                        }
+                        continue;
                     }
 
                     let mut dest_file = dest_path.clone();
@@ -262,7 +572,24 @@ pub fn run() -> io::Result<()> {
                     // TODO if not read only
                     let writer =
                         fs::File::create(dest_file).expect("output wav to create correctly.");
-                    let _ = wav.file.write(writer);
+                    let writer = io::BufWriter::new(writer);
+                    let result = match output_format {
+                        OutputFormat::Wav => wav.file.write(writer),
+                        OutputFormat::Aiff => {
+                            AiffFile::from_riff(&wav.file).and_then(|aiff| aiff.write(writer))
+                        }
+                        OutputFormat::Flac => {
+                            FlacFile::from_riff(&wav.file).and_then(|flac| flac.write(writer))
+                        }
+                        OutputFormat::Ogg => read_ogg(&source_dir.join(&wav.file.filename))
+                            .and_then(|mut ogg| {
+                                ogg.set_metadata(&wav.file.metadata()?)?;
+                                ogg.write(writer)
+                            }),
+                    };
+                    if let Err(e) = result {
+                        println!("\nError writing {}: {}.", wav.file.filename, e);
+                    }
                 }
 
                 // for mut wav in wavs {
@@ -349,6 +676,14 @@ pub fn run() -> io::Result<()> {
     Ok(())
 }
 
+/// Parse a number given by the user, exiting with an error if it isn't one.
+fn int_or_exit(s: &str) -> u32 {
+    str_to_int(s).unwrap_or_else(|e| {
+        println!("\nError: {}.", e);
+        std::process::exit(1);
+    })
+}
+
 pub fn get_input(prompt: &str) -> String {
     let mut s = String::new();
     print!("{}", prompt);
@@ -370,32 +705,202 @@ fn read_directory(path: PathBuf) -> io::Result<Vec<RiffFile>> {
         .iter()
         .filter_map(
             |pathbuf| match pathbuf.extension().and_then(|oss| oss.to_str()) {
-                Some("wav") => {
-                    let reader = fs::File::open(pathbuf).expect("file to open");
-                    let filename: String = (*pathbuf
-                        .file_name()
-                        .expect("filename to parse correctly")
-                        .to_string_lossy())
-                    .to_string();
-
-                    match RiffFile::read(reader, filename.clone()) {
-                        Ok(file) => Some(file),
-                        Err(e) => {
-                            panic!("Error parsing wav \"{}\": {}", filename, e)
-                        }
+                Some("wav") | Some("aif") | Some("aiff") | Some("flac") | Some("ogg")
+                | Some("oga") => match open_sample(pathbuf) {
+                    Ok(file) => Some(file),
+                    Err(e) => {
+                        println!("Error reading \"{}\": {}. skipping.", pathbuf.display(), e);
+                        None
                     }
-                }
+                },
                 _ => None,
             },
         )
         .collect())
 }
 
+fn read_aiff(path: &Path) -> Result<AiffFile, WavTagError> {
+    let filename = path
+        .file_name()
+        .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+
+    AiffFile::read(io::BufReader::new(fs::File::open(path)?), filename)
+}
+
+fn read_flac(path: &Path) -> Result<FlacFile, WavTagError> {
+    let filename = path
+        .file_name()
+        .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+
+    FlacFile::read(io::BufReader::new(fs::File::open(path)?), filename)
+}
+
+fn read_ogg(path: &Path) -> Result<OggFile, WavTagError> {
+    let filename = path
+        .file_name()
+        .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+
+    OggFile::read(io::BufReader::new(fs::File::open(path)?), filename)
+}
+
+/// Open a wav, or read an aiff, flac or ogg and convert it to wav.
+fn open_sample(path: &Path) -> Result<RiffFile, WavTagError> {
+    match path.extension().and_then(|oss| oss.to_str()) {
+        Some("aif") | Some("aiff") => RiffFile::from_aiff(&read_aiff(path)?),
+        Some("flac") => RiffFile::from_flac(&read_flac(path)?),
+        Some("ogg") | Some("oga") | Some("opus") => RiffFile::from_ogg(&read_ogg(path)?),
+        _ => RiffFile::open_lazy(path, LAZY_THRESHOLD),
+    }
+}
+
+/// The files in a directory with one of the given extensions, or the source itself if it is a
+/// file.
+fn audio_paths(source: &Path, extensions: &[&str]) -> io::Result<Vec<PathBuf>> {
+    if !source.is_dir() {
+        return Ok(vec![source.to_path_buf()]);
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(source)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .and_then(|oss| oss.to_str())
+            .is_some_and(|ext| extensions.contains(&ext))
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// Repair a wav into the destination directory, reporting what was fixed.
+fn repair_wav(path: &Path, dest_path: &Path) {
+    let filename = path
+        .file_name()
+        .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+
+    let repaired = fs::File::open(path)
+        .map_err(WavTagError::from)
+        .and_then(|file| RiffFile::repair(io::BufReader::new(file), filename.clone()));
+    let (wav, repairs) = match repaired {
+        Ok(repaired) => repaired,
+        Err(e) => {
+            println!("{}: Error: {}.", filename, e);
+            return;
+        }
+    };
+
+    if repairs.is_empty() {
+        println!("{}: nothing to repair.", filename);
+    } else {
+        println!("{}:", filename);
+        for repair in repairs {
+            println!("  {}.", repair);
+        }
+    }
+
+    let writer = match fs::File::create(dest_path.join(&filename)) {
+        Ok(writer) => writer,
+        Err(e) => {
+            println!("\nError writing {}: {}.", filename, e);
+            return;
+        }
+    };
+    if let Err(e) = wav.write(io::BufWriter::new(writer)) {
+        println!("\nError writing {}: {}.", filename, e);
+    }
+}
+
+/// Write the single-cycle waveforms in a directory, in filename order, to one wavetable.
+fn build_wavetable(source: &Path, dest_file: &Path, frame_size: u32) {
+    // opus audio can't be decoded yet.
+    let paths = match audio_paths(source, &["wav", "aif", "aiff", "flac", "ogg", "oga"]) {
+        Ok(paths) => paths,
+        Err(e) => {
+            println!("\nError: {}.", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut cycles = Vec::new();
+    let mut sample_rate = 44100;
+    for path in paths.iter() {
+        let audio = open_sample(path).and_then(|wav| {
+            if cycles.is_empty() {
+                sample_rate = wav.format()?.sample_rate;
+            }
+            wav.samples()
+        });
+        match audio {
+            Ok(audio) => cycles.push(audio),
+            Err(e) => {
+                println!("\nError reading \"{}\": {}.", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let filename = dest_file
+        .file_name()
+        .map_or_else(String::new, |f| f.to_string_lossy().into_owned());
+    let clm = WavetableChunk {
+        frame_size,
+        ..WavetableChunk::default()
+    };
+    let result = RiffFile::from_cycles(filename, &cycles, sample_rate, clm).and_then(|wav| {
+        let writer = fs::File::create(dest_file)?;
+        wav.write(io::BufWriter::new(writer))
+    });
+    match result {
+        Ok(()) => println!(
+            "{}: {} frames of {} sample frames.",
+            dest_file.display(),
+            cycles.len(),
+            frame_size
+        ),
+        Err(e) => {
+            println!("\nError writing {}: {}.", dest_file.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn print_wav(wav: RiffFile) {
     println!("{}, chunks: {:?}", wav.filename, wav.chunks.len());
 
-    for chunk in wav.chunks {
+    // wavetable frames are counted in sample frames of the data chunk.
+    let frames = match (wav.format(), wav.find_chunk_by_type(ChunkType::Data)) {
+        (Ok(format), Some(data)) => format.frame_count(data.len()),
+        _ => 0,
+    };
+    // the id3 chunk may be left in the source file if it holds artwork.
+    let id3 = wav.get_id3_chunk();
+
+    for chunk in wav.chunks.iter() {
+        // the data chunk is not printed, so it is left in the source file.
+        if chunk.header == ChunkType::Data {
+            println!("[{:?}]", chunk.header);
+            continue;
+        }
+        // chunks over the lazy threshold are read from the source file as they are printed.
+        let chunk = match wav.load_chunk(chunk) {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                println!("broken {:?} chunk detected: {}.", chunk.header, e);
+                continue;
+            }
+        };
         match chunk.header {
+            ChunkType::Format => {
+                if let Ok(format) = WaveFormat::from_chunk(&chunk) {
+                    println!("{:?}", format);
+                } else {
+                    println!("broken fmt chunk detected.");
+                }
+            }
             ChunkType::Instrument => {
                 if let Ok(inst) = InstrumentChunk::from_chunk(&chunk) {
                     println!("{:?}", inst);
@@ -410,10 +915,163 @@ fn print_wav(wav: RiffFile) {
                     println!("broken smpl chunk detected.");
                 }
             }
+            ChunkType::Acid => {
+                if let Ok(acid) = AcidChunk::from_chunk(&chunk) {
+                    println!("{:?}", acid);
+                } else {
+                    println!("broken acid chunk detected.");
+                }
+            }
+            ChunkType::Broadcast => {
+                if let Ok(bext) = BextChunk::from_chunk(&chunk) {
+                    println!("bext version {}", bext.version);
+                    println!("Description: {}", bext.description);
+                    println!("Originator: {}", bext.originator);
+                    println!("Originator Reference: {}", bext.originator_reference);
+                    println!(
+                        "Origination: {} {}",
+                        bext.origination_date, bext.origination_time
+                    );
+                    println!("Time Reference: {}", bext.time_reference);
+                    if bext.version >= 2 {
+                        println!(
+                            "Loudness: {:.2} LUFS, range {:.2} LU, true peak {:.2} dBTP",
+                            bext.loudness_value as f32 / 100.0,
+                            bext.loudness_range as f32 / 100.0,
+                            bext.max_true_peak_level as f32 / 100.0
+                        );
+                    }
+                    if !bext.coding_history.is_empty() {
+                        println!("Coding History: {}", bext.coding_history.trim_end());
+                    }
+                } else {
+                    println!("broken bext chunk detected.");
+                }
+            }
+            ChunkType::Ixml => {
+                if let Ok(ixml) = IxmlChunk::from_chunk(&chunk) {
+                    for field in ["PROJECT", "SCENE", "TAKE", "TAPE", "NOTE"] {
+                        if let Some(value) = ixml.get(field) {
+                            println!("{}: {}", field, value);
+                        }
+                    }
+                    for track in ixml.tracks() {
+                        println!("TRACK {}: {}", track.channel_index, track.name);
+                    }
+                } else {
+                    println!("broken iXML chunk detected.");
+                }
+            }
+            ChunkType::Wavetable => {
+                if let Ok(clm) = WavetableChunk::from_chunk(&chunk) {
+                    println!(
+                        "Wavetable: frame size {}, {} frames",
+                        clm.frame_size,
+                        clm.frame_count(frames)
+                    );
+                } else {
+                    println!("broken clm chunk detected.");
+                }
+            }
+            ChunkType::Id3 => match &id3 {
+                Ok(id3) => {
+                    println!("ID3v2.{}", id3.major_version);
+                    for frame in id3.frames.iter() {
+                        match id3.frame_text(frame) {
+                            Some(text) => {
+                                println!("{}: {}", String::from_utf8_lossy(&frame.id), text)
+                            }
+                            None => println!("[{}]", String::from_utf8_lossy(&frame.id)),
+                        }
+                    }
+                }
+                Err(_) => println!("broken id3 chunk detected."),
+            },
+            ChunkType::Cue => {
+                if let Ok(cue) = CueChunk::from_chunk(&chunk) {
+                    println!("{:?}", cue);
+                } else {
+                    println!("broken cue chunk detected.");
+                }
+            }
+            ChunkType::List => {
+                if let Ok(adtl) = AssociatedDataChunk::from_chunk(&chunk) {
+                    println!("{:?}", adtl);
+                } else if let Ok(info) = InfoChunk::from_chunk(&chunk) {
+                    for (key, value) in info.entries {
+                        println!("{}: {}", String::from_utf8_lossy(&key), value);
+                    }
+                } else {
+                    println!("[{:?}]", chunk.header);
+                }
+            }
             _ => println!("[{:?}]", chunk.header),
         }
     }
-    print!("\n");
+    println!();
+}
+
+fn print_aiff(aiff: AiffFile) {
+    println!("{}, chunks: {:?}", aiff.filename, aiff.chunks.len());
+
+    for chunk in aiff.chunks.iter() {
+        match &chunk.tag {
+            b"COMM" => {
+                if let Ok(common) = CommonChunk::from_chunk(chunk) {
+                    println!("{:?}", common);
+                } else {
+                    println!("broken COMM chunk detected.");
+                }
+            }
+            b"MARK" => {
+                if let Ok(mark) = MarkerChunk::from_chunk(chunk) {
+                    println!("{:?}", mark);
+                } else {
+                    println!("broken MARK chunk detected.");
+                }
+            }
+            b"INST" => {
+                if let Ok(inst) = AiffInstrumentChunk::from_chunk(chunk) {
+                    println!("{:?}", inst);
+                } else {
+                    println!("broken INST chunk detected.");
+                }
+            }
+            b"COMT" => {
+                if let Ok(comt) = CommentsChunk::from_chunk(chunk) {
+                    for comment in comt.comments {
+                        println!("Comment: {}", comment.text);
+                    }
+                } else {
+                    println!("broken COMT chunk detected.");
+                }
+            }
+            tag => println!("[{}]", String::from_utf8_lossy(tag)),
+        }
+    }
+    println!();
+}
+
+fn print_ogg(ogg: OggFile) {
+    println!("{}, {:?}", ogg.filename, ogg.codec);
+    println!(
+        "channels: {}, sample rate: {}, frames: {}",
+        ogg.channels(),
+        ogg.sample_rate(),
+        ogg.frames()
+    );
+
+    if let Ok(comments) = ogg.get_vorbis_comments() {
+        for (key, value) in comments.comments.iter() {
+            println!("{}: {}", key, value);
+        }
+        for sample_loop in comments.loops(ogg.frames()) {
+            println!("{:?}", sample_loop);
+        }
+    } else {
+        println!("broken comment header detected.");
+    }
+    println!();
 }
 
 // pub fn file_name(wav: &RiffFile, name: &str) -> String {
@@ -1,13 +1,15 @@
 mod commands;
 mod midi;
 
-const VERSION: &'static str = env!("CARGO_PKG_VERSION");
-const USAGE: &'static str = "
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const USAGE: &str = "
 🎹  ZODAK
 
 Usage:
-  zodak tag <source> <destdir> [--inst] [--smpl] [--sfz] [--guess-keymap] [--sfzinput=<file>] [--loop-start=<n>] [--loop-end=<n>] [--verbose]
+  zodak tag <source> (<destdir> | --in-place) [--inst] [--fine-tune=<cents>] [--gain=<db>] [--smpl] [--acid] [--tempo=<bpm>] [--beats=<n>] [--one-shot] [--sfz] [--guess-keymap] [--sfzinput=<file>] [--loop-start=<n>] [--loop-end=<n>] [--title=<s>] [--artist=<s>] [--comment=<s>] [--keywords=<s>] [--originator=<s>] [--sync-id3] [--pcm] [--format=<fmt>] [--verbose]
   zodak print <source>
+  zodak repair <source> <destdir>
+  zodak wavetable <source> <destfile> [--frame-size=<n>]
   zodak (-h | --help)
   zodak --version
 
@@ -18,12 +20,24 @@ Options:
   --overwrite           Prompt to overwrite tags already within the WAV source (default=off)
   --velocity            Prompt for a velocity range for each sample (default=off)
   --readonly
+  --in-place            Edit the tags of the source files rather than writing copies to <destdir>
+  --format=<fmt>        Output file format, wav, aiff, flac or ogg (ogg sources only) [default: wav]
+  --pcm                 Transcode µ-law, A-law and IMA ADPCM sources to 16-bit PCM without asking
+                        (always done for aiff and flac output)
   --verbose             Display more information during parsing
+  --frame-size=<n>      Sample frames in each wavetable frame [default: 2048]
 
   --guess-keymap        Attempt to guess a keymap based on filenames
 
   --inst                Add or edit instrument chunk
+  --fine-tune=<cents>   Fine tune for all files processed, -50 to +50 (instrument chunk)
+  --gain=<db>           Gain for all files processed, -64 to +64 (instrument chunk)
   --smpl                Add or edit sampler chunk
+  --acid                Add or edit acid chunk (tempo and root note for loop browsers)
+
+  --tempo=<bpm>         Tempo of the loops (acid chunk)
+  --beats=<n>           Number of beats in each loop (default=calculated from tempo)
+  --one-shot            Mark samples as one-shots rather than loops (no smpl loops, acid one-shot flag)
 
   --sfz                 Output an SFZ file with data from the input files
   --sfzinput=<file>     Use an SFZ as an override for all tags
@@ -31,6 +45,13 @@ Options:
   --loop-start=<n>           Override loop start for all files processed
   --loop-end=<n>             Override loop end for all files processed
 
+  --title=<s>           Title (INAM) for all files processed (default=instrument name)
+  --artist=<s>          Artist (IART) for all files processed
+  --comment=<s>         Comment (ICMT) for all files processed
+  --keywords=<s>        Keywords (IKEY) for all files processed, eg. Pad; Analog
+  --originator=<s>      Broadcast wave originator (bext) for all files processed
+  --sync-id3            Mirror the title, artist, comment, genre etc. between INFO and an id3 chunk
+
 ";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
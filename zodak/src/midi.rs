@@ -28,15 +28,15 @@ pub fn name_to_note_num(note_name:&str) -> u8 {
     notes.insert("a", 9);
     notes.insert("b", 11);
 
-    let base_note = *notes.get(&note.clone().to_lowercase().to_string().as_str()).expect(format!("note to convert to midi number: {}", note).as_str());
+    let base_note = *notes.get(&note.clone().to_lowercase().to_string().as_str()).unwrap_or_else(|| panic!("note to convert to midi number: {}", note));
 
     // adjust for octave
     let mut adjusted_note = base_note as i8 + (octave * 12);
 
     // account for sharps and flats
     match augment.as_str() {
-        "#" => { adjusted_note = adjusted_note + 1 },
-        "b"|"B" => { adjusted_note = adjusted_note - 1 },
+        "#" => { adjusted_note += 1 },
+        "b"|"B" => { adjusted_note -= 1 },
         _ => {},
     }
 